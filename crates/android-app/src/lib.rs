@@ -8,13 +8,12 @@ fn main() {
     app.add_plugins((
         DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
-                resizable: false,
                 mode: WindowMode::BorderlessFullscreen,
                 ..default()
             }),
             ..default()
         }),
-        DungeonsAndDiagramsPlugin::new(7, 13),
+        DungeonsAndDiagramsPlugin::default(),
     ));
 
     // MSAA makes some Android devices panic, this is under investigation
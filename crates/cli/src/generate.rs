@@ -0,0 +1,359 @@
+use std::{
+    fmt::Write as _,
+    ops::Range,
+    path::PathBuf,
+    process::ExitCode,
+    time::{Duration, Instant},
+};
+
+use rayon::prelude::*;
+
+use dnd_rs_level::{Difficulty, GenerationReport, Level, Pack, Puzzle, Solver};
+
+/// Generate a pack of puzzles that pass the given filters.
+///
+/// Every seed in the range is tried in order, generating one candidate level per seed,
+/// until enough puzzles are accepted. The same arguments always give the same pack.
+/// A summary of how many candidates each filter rejected is printed to stderr.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    #[arg(long, default_value_t = 8)]
+    width: usize,
+
+    #[arg(long, default_value_t = 8)]
+    height: usize,
+
+    /// Number of puzzles in the pack
+    #[arg(long, default_value_t = 10)]
+    count: usize,
+
+    /// Seeds to generate candidates from, as `START..END` or `START..`
+    #[arg(long, value_name = "RANGE", default_value = "0..", value_parser = parse_seeds)]
+    seeds: Range<u64>,
+
+    /// Only accept puzzles with a unique solution
+    #[arg(long)]
+    unique: bool,
+
+    /// Reject puzzles with 3x3, 2x4 or 4x2 blocks of walls
+    #[arg(long)]
+    no_big_wall_blocks: bool,
+
+    /// Reject puzzles easier than this (easy, medium or hard)
+    #[arg(long)]
+    min_difficulty: Option<Difficulty>,
+
+    /// Reject puzzles harder than this (easy, medium or hard)
+    #[arg(long)]
+    max_difficulty: Option<Difficulty>,
+
+    /// Include the solution of each puzzle in the pack
+    #[arg(long)]
+    with_solutions: bool,
+
+    /// File to write the pack to, instead of stdout
+    #[arg(long, short)]
+    output: Option<PathBuf>,
+}
+
+impl Args {
+    fn needs_solving(&self) -> bool {
+        self.unique || self.min_difficulty.is_some() || self.max_difficulty.is_some()
+    }
+}
+
+pub(crate) fn run(args: Args) -> Result<ExitCode, String> {
+    // Smaller boards can't be generated, so every candidate would fail
+    if args.width < 2 || args.height < 2 {
+        return Err("Width and height must be at least 2".to_string());
+    }
+    if let (Some(min), Some(max)) = (args.min_difficulty, args.max_difficulty) {
+        if min > max {
+            return Err(format!("Minimum difficulty {min} is above maximum {max}"));
+        }
+    }
+
+    let start = Instant::now();
+    let mut summary = Summary::default();
+    let mut accepted = Vec::new();
+    let mut seeds = args.seeds.clone();
+
+    // Candidates are checked in parallel batches, and accepted in seed order so the
+    // pack doesn't depend on the number of threads
+    let batch_size = rayon::current_num_threads() * 4;
+    while accepted.len() < args.count && !seeds.is_empty() {
+        let batch_end = seeds.start.saturating_add(batch_size as u64).min(seeds.end);
+        let candidates: Vec<(u64, Candidate, GenerationReport)> = (seeds.start..batch_end)
+            .into_par_iter()
+            .map(|seed| {
+                let (candidate, report) = check_candidate(&args, seed);
+                (seed, candidate, report)
+            })
+            .collect();
+        seeds.start = batch_end;
+
+        for (seed, candidate, report) in candidates {
+            if accepted.len() == args.count {
+                break;
+            }
+            summary.add(&candidate, &report);
+            if let Candidate::Accepted(level, difficulty) = candidate {
+                accepted.push((seed, level, difficulty));
+            }
+        }
+    }
+    summary.elapsed = start.elapsed();
+
+    let pack = Pack {
+        puzzles: accepted
+            .iter()
+            .map(|(seed, level, difficulty)| {
+                let mut puzzle = Puzzle::from_level(level);
+                puzzle.seed = Some(*seed);
+                puzzle.difficulty = *difficulty;
+                if !args.with_solutions {
+                    puzzle.solution = None;
+                }
+                puzzle
+            })
+            .collect(),
+    }
+    .to_string();
+    match &args.output {
+        Some(path) => std::fs::write(path, &pack)
+            .map_err(|err| format!("Could not write {}: {err}", path.display()))?,
+        None => print!("{pack}"),
+    }
+
+    eprint!("{}", summary.report(&args, &accepted));
+
+    if accepted.len() < args.count {
+        eprintln!(
+            "Only found {} of {} puzzles in the seed range",
+            accepted.len(),
+            args.count
+        );
+        Ok(ExitCode::FAILURE)
+    } else {
+        Ok(ExitCode::SUCCESS)
+    }
+}
+
+fn parse_seeds(value: &str) -> Result<Range<u64>, String> {
+    let Some((start, end)) = value.split_once("..") else {
+        return Err(format!("Expected a range like 0..1000, got {value}"));
+    };
+    let start = start
+        .parse::<u64>()
+        .map_err(|_| format!("Invalid start of seed range: {start}"))?;
+    let end = if end.is_empty() {
+        u64::MAX
+    } else {
+        end.parse::<u64>()
+            .map_err(|_| format!("Invalid end of seed range: {end}"))?
+    };
+    if start >= end {
+        return Err(format!("Seed range {value} is empty"));
+    }
+    Ok(start..end)
+}
+
+enum Candidate {
+    /// Accepted puzzles only have a difficulty when they had to be solved
+    Accepted(Level, Option<Difficulty>),
+    /// Rejected by the builder's checks, as counted in its report
+    Rejected,
+    WrongDifficulty,
+    Failed,
+}
+
+// One attempt of the level builder, so each seed gives one candidate
+fn check_candidate(args: &Args, seed: u64) -> (Candidate, GenerationReport) {
+    let mut builder = Level::builder(args.width, args.height);
+    if args.no_big_wall_blocks {
+        builder = builder.check_too_many_walls();
+    }
+    if args.unique {
+        builder = builder.check_unique_solution();
+    }
+
+    let Ok((level, report)) = builder.attempt(seed) else {
+        return (Candidate::Failed, GenerationReport::default());
+    };
+    let Some(level) = level else {
+        return (Candidate::Rejected, report);
+    };
+
+    if !args.needs_solving() {
+        return (Candidate::Accepted(level, None), report);
+    }
+
+    // Only rated by the builder when it checked the solution is unique
+    let difficulty = report
+        .difficulty
+        .unwrap_or_else(|| Solver::from_level(&level).difficulty());
+    if args.min_difficulty.is_some_and(|min| difficulty < min)
+        || args.max_difficulty.is_some_and(|max| difficulty > max)
+    {
+        return (Candidate::WrongDifficulty, report);
+    }
+
+    (Candidate::Accepted(level, Some(difficulty)), report)
+}
+
+#[derive(Default)]
+struct Summary {
+    /// Totals of the builder's reports, with the candidates it rejected
+    report: GenerationReport,
+    wrong_difficulty: usize,
+    failed: usize,
+    elapsed: Duration,
+}
+
+impl Summary {
+    fn add(&mut self, candidate: &Candidate, report: &GenerationReport) {
+        self.report.add(report);
+        match candidate {
+            Candidate::Accepted(..) | Candidate::Rejected => {}
+            Candidate::WrongDifficulty => self.wrong_difficulty += 1,
+            Candidate::Failed => self.failed += 1,
+        }
+    }
+
+    fn candidates(&self) -> usize {
+        self.report.attempts + self.failed
+    }
+
+    fn report(&self, args: &Args, accepted: &[(u64, Level, Option<Difficulty>)]) -> String {
+        let candidates = self.candidates();
+        let rate = |count: usize| 100.0 * count as f64 / candidates.max(1) as f64;
+        let mut report = String::new();
+
+        let _ = writeln!(
+            report,
+            "Generated {} {}x{} puzzles from {} candidates in {:.1?}",
+            accepted.len(),
+            args.width,
+            args.height,
+            candidates,
+            self.elapsed
+        );
+        let _ = writeln!(
+            report,
+            "  accepted:          {:>6} ({:.1}%)",
+            accepted.len(),
+            rate(accepted.len())
+        );
+        let rejections = [
+            (
+                "big wall blocks",
+                self.report.rejected_big_wall_blocks,
+                args.no_big_wall_blocks,
+            ),
+            ("not unique", self.report.rejected_not_unique, args.unique),
+            (
+                "wrong difficulty",
+                self.wrong_difficulty,
+                args.min_difficulty.is_some() || args.max_difficulty.is_some(),
+            ),
+            ("failed", self.failed, self.failed > 0),
+        ];
+        for (name, count, shown) in rejections {
+            if shown {
+                let _ = writeln!(
+                    report,
+                    "  {:<18} {count:>6} ({:.1}%)",
+                    format!("{name}:"),
+                    rate(count)
+                );
+            }
+        }
+
+        // Added up over the threads, so together they can be more than the elapsed time
+        let _ = writeln!(
+            report,
+            "  generating time:   {:.1?}",
+            self.report.generating_time
+        );
+        if args.unique {
+            let _ = writeln!(
+                report,
+                "  solving time:      {:.1?}",
+                self.report.solving_time
+            );
+        }
+
+        if let Some(first) = accepted.first() {
+            let last = accepted.last().unwrap_or(first);
+            let _ = writeln!(report, "  seeds used:        {}..={}", first.0, last.0);
+        }
+        for difficulty in [Difficulty::Easy, Difficulty::Medium, Difficulty::Hard] {
+            let count = accepted
+                .iter()
+                .filter(|(_, _, d)| *d == Some(difficulty))
+                .count();
+            if count > 0 {
+                let _ = writeln!(
+                    report,
+                    "  {:<18} {count:>6}",
+                    format!("{}:", difficulty.to_string().to_lowercase())
+                );
+            }
+        }
+
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_seeds() {
+        assert_eq!(parse_seeds("5..10"), Ok(5..10));
+        assert_eq!(parse_seeds("7.."), Ok(7..u64::MAX));
+        assert!(parse_seeds("10..5").is_err());
+        assert!(parse_seeds("10").is_err());
+    }
+
+    fn args(width: usize, height: usize) -> Args {
+        Args {
+            width,
+            height,
+            count: 1,
+            seeds: 0..1,
+            unique: true,
+            no_big_wall_blocks: true,
+            min_difficulty: None,
+            max_difficulty: None,
+            with_solutions: false,
+            output: None,
+        }
+    }
+
+    #[test]
+    fn test_too_small() {
+        assert!(run(args(1, 8)).is_err());
+        assert!(run(args(8, 1)).is_err());
+    }
+
+    #[test]
+    fn test_check_candidate() {
+        let args = args(8, 8);
+        let mut summary = Summary::default();
+        for seed in 0..20 {
+            let (candidate, report) = check_candidate(&args, seed);
+            assert_eq!(report.attempts, 1);
+            if let Candidate::Accepted(level, difficulty) = &candidate {
+                assert!(!level.has_big_wall_blocks());
+                assert_eq!(Solver::from_level(level).all_solutions().len(), 1);
+                assert_eq!(*difficulty, report.difficulty);
+                assert_eq!(report.seed, seed);
+            }
+            summary.add(&candidate, &report);
+        }
+        assert_eq!(summary.candidates(), 20);
+        assert!(summary.report.rejected_big_wall_blocks + summary.report.rejected_not_unique < 20);
+    }
+}
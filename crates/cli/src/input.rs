@@ -0,0 +1,49 @@
+use std::{
+    io::{self, Read},
+    path::{Path, PathBuf},
+};
+
+use dnd_rs_level::{Pack, Puzzle};
+
+/// A puzzle read from a file or stdin, with a name to report it by
+pub(crate) struct Entry {
+    pub(crate) name: String,
+    pub(crate) puzzle: Result<Puzzle, String>,
+}
+
+/// Reads the puzzles from the given files, or stdin if there are none or the path is `-`.
+/// Files are puzzle packs, holding one or more puzzles separated by blank lines.
+pub(crate) fn read_puzzles(paths: &[PathBuf]) -> Result<Vec<Entry>, String> {
+    let stdin = [PathBuf::from("-")];
+    let paths = if paths.is_empty() { &stdin[..] } else { paths };
+
+    let mut puzzles = Vec::new();
+    for path in paths {
+        let (name, text) = read_source(path)?;
+        let pack = Pack::read_each(&text);
+        let count = pack.len();
+        puzzles.extend(pack.into_iter().enumerate().map(|(i, puzzle)| Entry {
+            name: if count == 1 {
+                name.clone()
+            } else {
+                format!("{name}#{}", i + 1)
+            },
+            puzzle,
+        }));
+    }
+    Ok(puzzles)
+}
+
+fn read_source(path: &Path) -> Result<(String, String), String> {
+    if path == Path::new("-") {
+        let mut text = String::new();
+        io::stdin()
+            .read_to_string(&mut text)
+            .map_err(|err| format!("Could not read stdin: {err}"))?;
+        Ok(("<stdin>".to_string(), text))
+    } else {
+        let text = std::fs::read_to_string(path)
+            .map_err(|err| format!("Could not read {}: {err}", path.display()))?;
+        Ok((path.display().to_string(), text))
+    }
+}
@@ -0,0 +1,243 @@
+use std::{
+    path::PathBuf,
+    process::ExitCode,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use dnd_rs_level::{Level, Puzzle};
+
+use crate::input::{self, Entry};
+
+/// Solve puzzles and report whether their solution is unique.
+///
+/// Puzzles are read from puzzle packs: a line of column wall counts, then a line per
+/// row with its wall count and cells (`M` monster, `T` treasure, `?` unknown),
+/// separated by spaces. Metadata lines like `title: ...` may come before a puzzle.
+/// Exits with a failure status unless every puzzle has exactly one solution.
+#[derive(clap::Args)]
+pub(crate) struct Args {
+    /// Files to read puzzles from, several per file separated by blank lines. Reads
+    /// stdin when no files are given or the file is `-`.
+    files: Vec<PathBuf>,
+
+    /// Print every solution instead of only the first
+    #[arg(long)]
+    all: bool,
+
+    /// Give up on a puzzle after this many seconds
+    #[arg(long, value_name = "SECONDS", value_parser = parse_timeout)]
+    timeout: Option<Duration>,
+
+    /// Print the results as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+pub(crate) fn run(args: Args) -> Result<ExitCode, String> {
+    let puzzles = input::read_puzzles(&args.files)?;
+
+    let mut reports = Vec::new();
+    for entry in &puzzles {
+        let report = Report::new(entry, solve(&entry.puzzle, args.timeout), args.all);
+        if !args.json {
+            report.print();
+        }
+        reports.push(report);
+    }
+
+    if args.json {
+        let json = serde_json::to_string_pretty(&reports).map_err(|err| err.to_string())?;
+        println!("{json}");
+    }
+
+    if reports.iter().all(|r| r.status == Status::Unique) {
+        Ok(ExitCode::SUCCESS)
+    } else {
+        Ok(ExitCode::FAILURE)
+    }
+}
+
+fn parse_timeout(value: &str) -> Result<Duration, String> {
+    let seconds = value
+        .parse::<f64>()
+        .map_err(|_| format!("Not a number of seconds: {value}"))?;
+    Duration::try_from_secs_f64(seconds).map_err(|err| err.to_string())
+}
+
+enum Outcome {
+    Invalid(String),
+    TimedOut(Duration),
+    Solved(Vec<Level>),
+}
+
+fn solve(puzzle: &Result<Puzzle, String>, timeout: Option<Duration>) -> Outcome {
+    let solver = match puzzle {
+        Ok(puzzle) => puzzle.solver(),
+        Err(err) => return Outcome::Invalid(err.clone()),
+    };
+
+    // The search is cancelled when it takes too long, so it doesn't hold up the thread
+    // pool for the puzzles after it
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let solver = solver.cancel_on(cancelled.clone());
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(solver.all_solutions());
+    });
+    let solutions = match timeout {
+        Some(timeout) => receiver.recv_timeout(timeout).ok(),
+        None => receiver.recv().ok(),
+    };
+
+    match solutions {
+        Some(solutions) => Outcome::Solved(solutions),
+        None => {
+            cancelled.store(true, Ordering::Relaxed);
+            Outcome::TimedOut(timeout.unwrap_or_default())
+        }
+    }
+}
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    Unique,
+    Multiple,
+    Unsolvable,
+    Timeout,
+    Invalid,
+}
+
+#[derive(Serialize)]
+struct Report {
+    puzzle: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    status: Status,
+    /// Missing when the puzzle was not solved
+    #[serde(skip_serializing_if = "Option::is_none")]
+    solution_count: Option<usize>,
+    /// The first solution or all of them, each as a list of rows
+    solutions: Vec<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl Report {
+    fn new(entry: &Entry, outcome: Outcome, all: bool) -> Self {
+        let mut report = Report {
+            puzzle: entry.name.clone(),
+            title: entry.puzzle.as_ref().ok().and_then(|p| p.title.clone()),
+            status: Status::Invalid,
+            solution_count: None,
+            solutions: Vec::new(),
+            error: None,
+        };
+
+        match outcome {
+            Outcome::Invalid(err) => report.error = Some(err),
+            Outcome::TimedOut(timeout) => {
+                report.status = Status::Timeout;
+                report.error = Some(format!("Timed out after {timeout:?}"));
+            }
+            Outcome::Solved(solutions) => {
+                report.status = match solutions.len() {
+                    0 => Status::Unsolvable,
+                    1 => Status::Unique,
+                    _ => Status::Multiple,
+                };
+                report.solution_count = Some(solutions.len());
+                let shown = if all { solutions.len() } else { 1 };
+                report.solutions = solutions.iter().take(shown).map(rows).collect();
+            }
+        }
+
+        report
+    }
+
+    fn print(&self) {
+        let summary = match (self.status, self.solution_count) {
+            (Status::Unique, _) => "unique solution".to_string(),
+            (Status::Multiple, Some(count)) => format!("{count} solutions"),
+            (Status::Unsolvable, _) => "no solution".to_string(),
+            _ => self.error.clone().unwrap_or_default(),
+        };
+        match &self.title {
+            Some(title) => println!("{} ({title}): {summary}", self.puzzle),
+            None => println!("{}: {summary}", self.puzzle),
+        }
+
+        for solution in &self.solutions {
+            println!();
+            for row in solution {
+                println!("{row}");
+            }
+        }
+        println!();
+    }
+}
+
+fn rows(level: &Level) -> Vec<String> {
+    format!("{level:?}").lines().map(str::to_string).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PUZZLE: &str = r#"
+  3 2 2 5 4 2 4 5
+6 M ? M ? ? ? ? ?
+4 ? ? ? M ? ? ? ?
+4 ? ? ? ? ? ? T ?
+3 ? ? M ? ? ? ? ?
+3 M ? ? ? ? ? ? ?
+3 ? ? ? M ? ? ? M
+3 M ? ? ? ? ? ? ?
+1 ? ? ? ? ? ? ? M
+"#;
+
+    fn report(text: &str, timeout: Option<Duration>) -> Report {
+        let entry = Entry {
+            name: "test".to_string(),
+            puzzle: Puzzle::try_from(text),
+        };
+        Report::new(&entry, solve(&entry.puzzle, timeout), true)
+    }
+
+    #[test]
+    fn test_unique_solution() {
+        let report = report(PUZZLE, None);
+        assert_eq!(report.status, Status::Unique);
+        assert_eq!(report.solution_count, Some(1));
+        assert_eq!(report.solutions[0][0], "M#M#####");
+    }
+
+    #[test]
+    fn test_unsolvable() {
+        let report = report(&PUZZLE.replace("\n1 ?", "\n2 ?"), None);
+        assert_eq!(report.status, Status::Unsolvable);
+        assert!(report.solutions.is_empty());
+    }
+
+    #[test]
+    fn test_invalid() {
+        let report = report("1 0\n1 ? X\n", None);
+        assert_eq!(report.status, Status::Invalid);
+        assert!(report.error.is_some());
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        assert_eq!(parse_timeout("1.5"), Ok(Duration::from_millis(1500)));
+        assert!(parse_timeout("-1").is_err());
+        assert!(parse_timeout("soon").is_err());
+    }
+}
@@ -1,18 +1,47 @@
 #![windows_subsystem = "windows"]
 
 use bevy::prelude::*;
-use dnd_rs_plugin::DungeonsAndDiagramsPlugin;
+use dnd_rs_plugin::{DungeonsAndDiagramsPlugin, Puzzle};
 
+/// Run with a share code as argument to play that puzzle, or with `--paste` to play the
+/// share code on the clipboard
 fn main() {
-    App::new()
-        .add_plugins((
-            DefaultPlugins,
-            // To update asset meta run with `cargo run --features bevy/asset_processor`
-            // DefaultPlugins.set(AssetPlugin {
-            //     mode: AssetMode::Processed,
-            //     ..Default::default()
-            // }),
-            DungeonsAndDiagramsPlugin::default(),
-        ))
-        .run();
+    let mut plugin = DungeonsAndDiagramsPlugin::default();
+    let mut app = App::new();
+    match shared_puzzle() {
+        Ok(Some(puzzle)) => plugin = plugin.with_shared_puzzle(puzzle),
+        Ok(None) => {}
+        // Logged once the log plugin is set up, as there is no console on Windows
+        Err(err) => {
+            app.add_systems(Startup, move || warn!("{err}"));
+        }
+    }
+
+    app.add_plugins((
+        DefaultPlugins,
+        // To update asset meta run with `cargo run --features bevy/asset_processor`
+        // DefaultPlugins.set(AssetPlugin {
+        //     mode: AssetMode::Processed,
+        //     ..Default::default()
+        // }),
+        plugin,
+    ))
+    .run();
+}
+
+fn shared_puzzle() -> Result<Option<Puzzle>, String> {
+    let Some(arg) = std::env::args().nth(1) else {
+        return Ok(None);
+    };
+    let code = if arg == "--paste" {
+        arboard::Clipboard::new()
+            .and_then(|mut clipboard| clipboard.get_text())
+            .map_err(|err| format!("Could not read the clipboard: {err}"))?
+    } else {
+        arg
+    };
+
+    Puzzle::from_share_code(&code)
+        .map(Some)
+        .map_err(|err| format!("Could not read share code: {err}"))
 }
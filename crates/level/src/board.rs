@@ -0,0 +1,617 @@
+use std::collections::VecDeque;
+
+use crate::{
+    grid::{Grid, GridPos},
+    CellFloor, CellKind, Level, Solver,
+};
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Mark {
+    #[default]
+    Empty,
+    Wall,
+    Hallway,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum ViolationKind {
+    /// A monster with walls on all sides
+    EnclosedMonster,
+    /// A hallway ending in a cell without a monster
+    DeadEnd,
+    /// A 2x2 block of hallway outside of the treasure room
+    WideHallway,
+    /// A hallway region that can't connect to the rest of the dungeon
+    CutOff,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Violation {
+    position: GridPos,
+    kind: ViolationKind,
+}
+
+impl Violation {
+    pub fn x(&self) -> usize {
+        self.position.x
+    }
+
+    pub fn y(&self) -> usize {
+        self.position.y
+    }
+
+    pub fn kind(&self) -> ViolationKind {
+        self.kind
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HintArea {
+    Row(usize),
+    Column(usize),
+    Cell,
+}
+
+/// Which rule the marks placed so far break
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Contradiction {
+    /// The walls in the row don't fit its count
+    Row(usize),
+    /// The walls in the column don't fit its count
+    Column(usize),
+    /// No way of finishing the board follows the monster, treasure and hallway rules
+    Rules,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hint {
+    /// Some of the marks placed so far can't be part of any solution
+    Mistake(Contradiction),
+    /// The mark at `x`, `y` follows from the rules, looking at `area`
+    Deduction {
+        area: HintArea,
+        x: usize,
+        y: usize,
+        mark: Mark,
+    },
+}
+
+/// The player's view of a level: the clues from the level plus the marks placed so far
+#[derive(Clone)]
+pub struct Board {
+    level: Level,
+    marks: Grid<Mark>,
+    row_walls: Vec<usize>,
+    col_walls: Vec<usize>,
+}
+
+impl Board {
+    pub fn new(level: &Level) -> Self {
+        let mut col_walls = vec![0; level.width()];
+        let mut row_walls = vec![0; level.height()];
+        level.iter().for_each(|c| {
+            if c.has_wall() {
+                col_walls[c.x()] += 1;
+                row_walls[c.y()] += 1;
+            }
+        });
+
+        Self {
+            level: level.clone(),
+            marks: Grid::new(level.width(), level.height(), Mark::Empty),
+            row_walls,
+            col_walls,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.marks.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.marks.height()
+    }
+
+    pub fn mark(&self, x: usize, y: usize) -> Mark {
+        self.marks[(x, y).into()]
+    }
+
+    pub fn set_mark(&mut self, x: usize, y: usize, mark: Mark) {
+        self.marks[(x, y).into()] = mark;
+    }
+
+    /// Marks that disagree with the solution of the level
+    pub fn mistakes(&self) -> Vec<(usize, usize)> {
+        self.marks
+            .iter()
+            .filter(|&(&mark, pos)| match mark {
+                Mark::Wall => !self.level.grid[pos].has_wall(),
+                Mark::Hallway => self.level.grid[pos].has_wall(),
+                Mark::Empty => false,
+            })
+            .map(|(_, pos)| (pos.x, pos.y))
+            .collect()
+    }
+
+    pub(crate) fn level(&self) -> &Level {
+        &self.level
+    }
+
+    /// Whether the walls placed so far follow all the rules, with every other cell as
+    /// hallway. Any solution of the puzzle counts, not only the level's own.
+    pub fn is_solved(&self) -> bool {
+        Solver::is_solved(self)
+    }
+
+    /// Find the next cell that can be deduced from the marks placed so far,
+    /// or `None` when there is nothing left to deduce
+    pub fn hint(&self) -> Option<Hint> {
+        Solver::hint(self)
+    }
+
+    fn is_monster(&self, pos: GridPos) -> bool {
+        self.level.grid[pos].kind == CellKind::Floor(CellFloor::Monster)
+    }
+
+    fn is_treasure(&self, pos: GridPos) -> bool {
+        self.level.grid[pos].kind == CellKind::Floor(CellFloor::Treasure)
+    }
+
+    /// Cells the player has established to be part of the dungeon floor
+    fn is_open(&self, pos: GridPos) -> bool {
+        if self.is_monster(pos) || self.is_treasure(pos) {
+            return true;
+        }
+        match self.marks[pos] {
+            Mark::Wall => return false,
+            Mark::Hallway => return true,
+            Mark::Empty => {}
+        }
+
+        let is_wall = |m: &Mark| m == &Mark::Wall;
+        self.marks.count_row(pos.y, is_wall) == self.row_walls[pos.y]
+            || self.marks.count_col(pos.x, is_wall) == self.col_walls[pos.x]
+    }
+
+    /// Whether the 2x2 block at `pos` fits inside a treasure room around some treasure
+    fn is_in_possible_treasure_room(&self, pos: GridPos) -> bool {
+        self.level
+            .iter()
+            .filter(|c| c.kind == CellKind::Floor(CellFloor::Treasure))
+            .any(|treasure| {
+                let t = treasure.position;
+                (t.x.saturating_sub(2)..=t.x).any(|room_x| {
+                    room_x + 2 < self.width()
+                        && room_x <= pos.x
+                        && pos.x < room_x + 2
+                        && (t.y.saturating_sub(2)..=t.y).any(|room_y| {
+                            room_y + 2 < self.height() && room_y <= pos.y && pos.y < room_y + 2
+                        })
+                })
+            })
+    }
+
+    /// Find cells breaking the rules given the marks placed so far
+    pub fn violations(&self) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let mut push = |position: GridPos, kind| violations.push(Violation { position, kind });
+
+        for (&mark, pos) in self.marks.iter() {
+            if mark == Mark::Wall {
+                continue;
+            }
+
+            let open_neighbors = self.marks.count_neighbors(pos, |n| n != &Mark::Wall);
+            if self.is_monster(pos) {
+                if open_neighbors == 0 {
+                    push(pos, ViolationKind::EnclosedMonster);
+                }
+            } else if open_neighbors <= 1 && self.is_open(pos) {
+                push(pos, ViolationKind::DeadEnd);
+            }
+
+            if pos.x + 1 < self.width()
+                && pos.y + 1 < self.height()
+                && [(0, 0), (1, 0), (0, 1), (1, 1)]
+                    .into_iter()
+                    .all(|(dx, dy)| self.is_open((pos.x + dx, pos.y + dy).into()))
+                && !self.is_in_possible_treasure_room(pos)
+            {
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    push((pos.x + dx, pos.y + dy).into(), ViolationKind::WideHallway);
+                }
+            }
+        }
+
+        // Split the non-wall cells into connected regions, only keeping those known to hold floor
+        let mut visited = Grid::new(self.width(), self.height(), false);
+        let mut regions: Vec<Vec<GridPos>> = Vec::new();
+        for (&mark, start) in self.marks.iter() {
+            if mark == Mark::Wall || visited[start] {
+                continue;
+            }
+
+            let mut region = Vec::new();
+            let mut has_floor = false;
+            let mut queue = VecDeque::from([start]);
+            visited[start] = true;
+            while let Some(pos) = queue.pop_front() {
+                has_floor |= self.is_open(pos);
+                region.push(pos);
+                for n in self.marks.iter_neighbors(pos) {
+                    if !visited[n] && self.marks[n] != Mark::Wall {
+                        visited[n] = true;
+                        queue.push_back(n);
+                    }
+                }
+            }
+
+            if has_floor {
+                regions.push(region);
+            }
+        }
+
+        // Everything but the largest region is cut off from the rest of the dungeon
+        if let Some(largest) = (0..regions.len()).max_by_key(|&i| regions[i].len()) {
+            regions.swap_remove(largest);
+            for pos in regions.into_iter().flatten() {
+                push(pos, ViolationKind::CutOff);
+            }
+        }
+
+        violations.sort();
+        violations.dedup();
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solver::SolverLevel;
+
+    fn level(s: &str) -> Level {
+        Level::from(&SolverLevel::try_from(s).unwrap())
+    }
+
+    fn solved_board(level: &Level) -> Board {
+        let mut board = Board::new(level);
+        level
+            .iter()
+            .filter(|c| c.has_wall())
+            .for_each(|c| board.set_mark(c.x(), c.y(), Mark::Wall));
+        board
+    }
+
+    fn kinds_at(violations: &[Violation], x: usize, y: usize) -> Vec<ViolationKind> {
+        violations
+            .iter()
+            .filter(|v| v.x() == x && v.y() == y)
+            .map(|v| v.kind())
+            .collect()
+    }
+
+    #[test]
+    fn test_solved_board_has_no_violations() {
+        let level = level(
+            r#"
+...#M.M#
+...##.#M
+..T.....
+###.#.#M
+....#..#
+M##.M#.M
+#...#M.#
+M.#.M#.M
+"#,
+        );
+
+        assert_eq!(solved_board(&level).violations(), vec![]);
+    }
+
+    #[test]
+    fn test_empty_board_has_no_violations() {
+        let level = level(
+            r#"
+M#M#####
+...M####
+#.##..T#
+#.M#...#
+M.##...#
+#..M#.#M
+M#.##...
+......#M
+"#,
+        );
+
+        assert_eq!(Board::new(&level).violations(), vec![]);
+    }
+
+    #[test]
+    fn test_enclosed_monster() {
+        let level = level(
+            r#"
+M.#
+#..
+.#M
+"#,
+        );
+        let mut board = Board::new(&level);
+        board.set_mark(1, 0, Mark::Wall);
+        board.set_mark(0, 1, Mark::Wall);
+
+        let violations = board.violations();
+        assert!(kinds_at(&violations, 0, 0).contains(&ViolationKind::EnclosedMonster));
+        assert!(!kinds_at(&violations, 2, 2).contains(&ViolationKind::EnclosedMonster));
+    }
+
+    #[test]
+    fn test_dead_end() {
+        let level = level(
+            r#"
+M..
+##.
+M..
+"#,
+        );
+        let mut board = solved_board(&level);
+        board.set_mark(0, 1, Mark::Empty);
+        board.set_mark(2, 0, Mark::Wall);
+
+        // Middle column is satisfied, so the top cell is known to be floor and now a dead end
+        let violations = board.violations();
+        assert_eq!(kinds_at(&violations, 1, 0), vec![ViolationKind::DeadEnd]);
+    }
+
+    #[test]
+    fn test_wide_hallway() {
+        let level = level(
+            r#"
+M...
+##..
+M...
+"#,
+        );
+
+        let violations = solved_board(&level).violations();
+        for (x, y) in [(2, 0), (3, 0), (2, 1), (3, 1), (2, 2), (3, 2)] {
+            assert!(
+                kinds_at(&violations, x, y).contains(&ViolationKind::WideHallway),
+                "Expected wide hallway at ({x}, {y}): {violations:?}"
+            );
+        }
+        assert!(!kinds_at(&violations, 1, 0).contains(&ViolationKind::WideHallway));
+    }
+
+    #[test]
+    fn test_treasure_room_is_not_wide_hallway() {
+        let level = level(
+            r#"
+...#M
+.T...
+...#M
+"#,
+        );
+
+        assert_eq!(solved_board(&level).violations(), vec![]);
+    }
+
+    #[test]
+    fn test_hallway_marks_are_open() {
+        let level = level(
+            r#"
+M#M#####
+...M####
+#.##..T#
+#.M#...#
+M.##...#
+#..M#.#M
+M#.##...
+......#M
+"#,
+        );
+        let mut board = Board::new(&level);
+        for (x, y) in [(4, 6), (5, 6), (4, 7), (5, 7)] {
+            board.set_mark(x, y, Mark::Hallway);
+        }
+
+        let violations = board.violations();
+        for (x, y) in [(4, 6), (5, 6), (4, 7), (5, 7)] {
+            assert!(kinds_at(&violations, x, y).contains(&ViolationKind::WideHallway));
+        }
+    }
+
+    #[test]
+    fn test_cut_off_region() {
+        let level = level(
+            r#"
+M...
+##.#
+M...
+"#,
+        );
+        let mut board = solved_board(&level);
+        board.set_mark(2, 1, Mark::Wall);
+
+        let violations = board.violations();
+        let cut_off = violations
+            .iter()
+            .filter(|v| v.kind() == ViolationKind::CutOff)
+            .map(|v| (v.x(), v.y()))
+            .collect::<Vec<_>>();
+        assert_eq!(cut_off.len(), 4, "{violations:?}");
+        assert!(cut_off.iter().all(|&(_, y)| y == cut_off[0].1));
+    }
+
+    #[test]
+    fn test_mistakes() {
+        let level = level(
+            r#"
+M.###
+.....
+...#M
+"#,
+        );
+        let mut board = Board::new(&level);
+        board.set_mark(2, 0, Mark::Wall);
+        board.set_mark(1, 0, Mark::Hallway);
+        assert_eq!(board.mistakes(), vec![]);
+
+        board.set_mark(1, 1, Mark::Wall);
+        board.set_mark(3, 2, Mark::Hallway);
+        assert_eq!(board.mistakes(), vec![(1, 1), (3, 2)]);
+    }
+
+    #[test]
+    fn test_is_solved() {
+        let solution = level(
+            r#"
+M#..M
+...##
+##...
+M..#M
+"#,
+        );
+        // The same puzzle, mirrored top to bottom
+        let other = level(
+            r#"
+M..#M
+##...
+...##
+M#..M
+"#,
+        );
+
+        let mut board = Board::new(&solution);
+        assert!(!board.is_solved());
+        board.set_mark(1, 0, Mark::Wall);
+        assert!(!board.is_solved());
+
+        let mut board = Board::new(&solution);
+        for c in other.iter().filter(|c| c.has_wall()) {
+            board.set_mark(c.x(), c.y(), Mark::Wall);
+        }
+        assert!(!board.mistakes().is_empty());
+        assert!(board.is_solved());
+        assert!(solved_board(&solution).is_solved());
+    }
+
+    #[test]
+    fn test_hint_row() {
+        let level = level(
+            r#"
+M.###
+#.#M#
+#...M
+"#,
+        );
+        // Row and column counts leave only walls for the rest of the top row
+        assert_eq!(
+            Board::new(&level).hint(),
+            Some(Hint::Deduction {
+                area: HintArea::Row(0),
+                x: 2,
+                y: 0,
+                mark: Mark::Wall,
+            })
+        );
+    }
+
+    #[test]
+    fn test_hint_mistake() {
+        let level = level(
+            r#"
+M.###
+#.#M#
+#...M
+"#,
+        );
+        let mut board = Board::new(&level);
+        board.set_mark(1, 1, Mark::Wall);
+        assert_eq!(board.hint(), Some(Hint::Mistake(Contradiction::Column(1))));
+
+        // Leaves the top row without room for its walls
+        let mut board = Board::new(&level);
+        board.set_mark(2, 0, Mark::Hallway);
+        assert_eq!(board.hint(), Some(Hint::Mistake(Contradiction::Row(0))));
+    }
+
+    #[test]
+    fn test_hint_mistake_fitting_counts() {
+        let level = level(
+            r#"
+M#..M
+...##
+##...
+M..#M
+"#,
+        );
+        // Every count can still be met, but only with a wide hallway
+        let mut board = Board::new(&level);
+        board.set_mark(1, 0, Mark::Hallway);
+        board.set_mark(1, 3, Mark::Hallway);
+        assert_eq!(board.hint(), Some(Hint::Mistake(Contradiction::Rules)));
+    }
+
+    #[test]
+    fn test_hint_other_solution() {
+        let solution = level(
+            r#"
+M#..M
+...##
+##...
+M..#M
+"#,
+        );
+        let other = level(
+            r#"
+M..#M
+##...
+...##
+M#..M
+"#,
+        );
+
+        // Following the rules towards the mirrored solution still gets hints
+        let mut board = Board::new(&solution);
+        board.set_mark(3, 0, Mark::Wall);
+        assert!(!board.mistakes().is_empty());
+        while let Some(hint) = board.hint() {
+            let Hint::Deduction { x, y, mark, .. } = hint else {
+                panic!("Unexpected hint {hint:?} for board:\n{:?}", board.marks);
+            };
+            assert_eq!(mark == Mark::Wall, other.is_wall(x, y));
+            board.set_mark(x, y, mark);
+        }
+        assert!(board.is_solved());
+    }
+
+    #[test]
+    fn test_hints_solve_level() {
+        let level = level(
+            r#"
+##M#...#
+#....#.M
+M.#M#..#
+####..#M
+.T.#.#..
+.....#.#
+...#....
+#####M#M
+"#,
+        );
+        let mut board = Board::new(&level);
+
+        while let Some(hint) = board.hint() {
+            let Hint::Deduction { x, y, mark, .. } = hint else {
+                panic!("Unexpected hint {hint:?} for board:\n{:?}", board.marks);
+            };
+            assert_eq!(mark == Mark::Wall, level.is_wall(x, y));
+            board.set_mark(x, y, mark);
+        }
+
+        assert!(level
+            .iter()
+            .all(|c| (board.mark(c.x(), c.y()) == Mark::Wall) == c.has_wall()));
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::NaiveDate;
+
+use crate::{fnv1a, LevelBuilder};
+
+pub const DAILY_WIDTH: usize = 8;
+pub const DAILY_HEIGHT: usize = 8;
+
+/// Seed for the daily level of a UTC date, the same on every platform
+pub fn daily_seed(date: NaiveDate) -> u64 {
+    fnv1a(date.format("%Y-%m-%d").to_string().as_bytes())
+}
+
+impl LevelBuilder {
+    /// Builder for the daily level, which everyone gets the same of on a given date
+    pub fn daily(date: NaiveDate) -> Self {
+        LevelBuilder::new(DAILY_WIDTH, DAILY_HEIGHT)
+            .seed(daily_seed(date))
+            .check_too_many_walls()
+            .check_unique_solution()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn test_daily_seed() {
+        assert_eq!(daily_seed(date(2024, 9, 1)), daily_seed(date(2024, 9, 1)));
+        assert_ne!(daily_seed(date(2024, 9, 1)), daily_seed(date(2024, 9, 2)));
+
+        // Changing the seed changes everyone's daily level
+        assert_eq!(daily_seed(date(2024, 9, 1)), 10820898367323707073);
+    }
+
+    #[test]
+    fn test_daily_level() {
+        let level = LevelBuilder::daily(date(2024, 9, 1)).build().unwrap();
+
+        assert_eq!(level.width(), DAILY_WIDTH);
+        assert_eq!(level.height(), DAILY_HEIGHT);
+        assert!(level == LevelBuilder::daily(date(2024, 9, 1)).build().unwrap());
+        assert!(level != LevelBuilder::daily(date(2024, 9, 2)).build().unwrap());
+
+        // Has to match on every platform, including 32-bit wasm
+        assert_eq!(
+            format!("{:?}", level).trim(),
+            r#"
+#M#M#...
+M.....#.
+###.###M
+M......#
+#.##...#
+...#..T#
+.#..###M
+.M#....."#
+                .trim()
+        );
+    }
+}
@@ -32,17 +32,15 @@ impl Debug for GenCell {
 pub(crate) type GenLevel = Grid<GenCell>;
 
 impl GenLevel {
-    pub fn random(width: usize, height: usize) -> Result<Self, &'static str> {
+    pub fn random(width: usize, height: usize, rng: &mut Rng) -> Result<Self, &'static str> {
         if width <= 1 || height <= 1 {
             return Err("Width and height must be greater than 1");
         }
 
         let mut grid = Grid::new(width, height, GenCell::Any);
-        let mut rng = Rng::new();
-
         let initial_work_queue = if width >= 6 && height >= 6 {
-            let treasure_room_x = rng.usize(0..width - 2);
-            let treasure_room_y = rng.usize(0..height - 2);
+            let treasure_room_x = pick(rng, width - 2);
+            let treasure_room_y = pick(rng, height - 2);
 
             // Fill treasure room with floor
             (treasure_room_x..treasure_room_x + 3).for_each(|x| {
@@ -51,8 +49,8 @@ impl GenLevel {
                 });
             });
 
-            let treasure_x = rng.usize(0..3) + treasure_room_x;
-            let treasure_y = rng.usize(0..3) + treasure_room_y;
+            let treasure_x = pick(rng, 3) + treasure_room_x;
+            let treasure_y = pick(rng, 3) + treasure_room_y;
             grid[(treasure_x, treasure_y).into()] = GenCell::Floor(GenFloor::Treasure);
 
             let mut potential_exits: Vec<GridPos> = Vec::new();
@@ -103,11 +101,11 @@ impl GenLevel {
                 })
             }
 
-            let exit = potential_exits[rng.usize(0..potential_exits.len())];
+            let exit = potential_exits[pick(rng, potential_exits.len())];
             grid[exit] = GenCell::Any;
             vec![exit]
         } else {
-            let random_floor_pos = (rng.usize(0..width), rng.usize(0..height)).into();
+            let random_floor_pos = (pick(rng, width), pick(rng, height)).into();
             grid[random_floor_pos] = GenCell::Floor(GenFloor::Empty);
             grid.iter_neighbors(random_floor_pos).collect()
         };
@@ -133,13 +131,19 @@ impl GenLevel {
     }
 }
 
-struct WorkQueue {
-    rng: Rng,
+// Random index below `n`. `Rng::usize` draws differently on 32-bit targets like wasm,
+// so stick to `u32` to make seeded levels the same on every platform.
+fn pick(rng: &mut Rng, n: usize) -> usize {
+    rng.u32(0..n as u32) as usize
+}
+
+struct WorkQueue<'a> {
+    rng: &'a mut Rng,
     vec: Vec<GridPos>,
 }
 
-impl WorkQueue {
-    fn new(rng: Rng) -> Self {
+impl<'a> WorkQueue<'a> {
+    fn new(rng: &'a mut Rng) -> Self {
         Self {
             rng,
             vec: Vec::new(),
@@ -155,7 +159,7 @@ impl WorkQueue {
             return None;
         }
 
-        let idx = self.rng.usize(0..self.vec.len());
+        let idx = pick(self.rng, self.vec.len());
         let p = self.vec.swap_remove(idx);
         Some(p)
     }
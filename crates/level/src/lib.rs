@@ -1,12 +1,29 @@
-use std::fmt::Debug;
+use std::{
+    fmt::{Debug, Display},
+    str::FromStr,
+    time::Duration,
+};
+
+use fastrand::Rng;
+use log::info;
+
+mod board;
+pub use board::{Board, Contradiction, Hint, HintArea, Mark, Violation, ViolationKind};
+
+mod daily;
+pub use daily::{daily_seed, DAILY_HEIGHT, DAILY_WIDTH};
 
 mod gen;
-use bevy::log::info;
 use gen::{GenCell, GenFloor, GenLevel};
 
 mod grid;
 use grid::{Grid, GridPos};
 
+mod pack;
+pub use pack::{Pack, Puzzle};
+
+mod share;
+
 mod solver;
 pub use solver::Solver;
 
@@ -23,6 +40,53 @@ pub enum CellKind {
     Floor(CellFloor),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    Easy,
+    Medium,
+    Hard,
+}
+
+// Based on how many guesses the solver needs on typical 8x8 levels
+const GUESSES_EASY: usize = 4;
+const GUESSES_MEDIUM: usize = 40;
+
+impl Difficulty {
+    fn from_guesses(guesses: usize) -> Self {
+        match guesses {
+            0..=GUESSES_EASY => Difficulty::Easy,
+            _ if guesses <= GUESSES_MEDIUM => Difficulty::Medium,
+            _ => Difficulty::Hard,
+        }
+    }
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Medium => "Medium",
+            Difficulty::Hard => "Hard",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "easy" => Ok(Difficulty::Easy),
+            "medium" => Ok(Difficulty::Medium),
+            "hard" => Ok(Difficulty::Hard),
+            _ => Err(format!(
+                "Unknown difficulty {s}, expected easy, medium or hard"
+            )),
+        }
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Cell {
     kind: CellKind,
@@ -65,15 +129,25 @@ impl Debug for Level {
     }
 }
 
-#[derive(bevy::prelude::Component, Clone, PartialEq)]
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "bevy", derive(bevy::prelude::Component))]
 pub struct Level {
     grid: Grid<Cell>,
 }
 
 impl Level {
     pub fn random(width: usize, height: usize) -> Result<Self, &'static str> {
+        Self::random_with_rng(width, height, &mut Rng::new())
+    }
+
+    /// Same as [`Level::random`], but the same seed always gives the same level
+    pub fn random_with_seed(width: usize, height: usize, seed: u64) -> Result<Self, &'static str> {
+        Self::random_with_rng(width, height, &mut Rng::with_seed(seed))
+    }
+
+    fn random_with_rng(width: usize, height: usize, rng: &mut Rng) -> Result<Self, &'static str> {
         let level_start = chrono::Utc::now();
-        let level = GenLevel::random(width, height)?.into();
+        let level = GenLevel::random(width, height, rng)?.into();
         info!(
             "Generated level in {:?}",
             chrono::Utc::now()
@@ -103,6 +177,34 @@ impl Level {
     pub fn is_wall(&self, x: usize, y: usize) -> bool {
         self.grid[(x, y).into()].has_wall()
     }
+
+    /// Whether the level has a 3x3, 2x4 or 4x2 block of walls, which makes for a dull puzzle
+    pub fn has_big_wall_blocks(&self) -> bool {
+        self.big_wall_block().is_some()
+    }
+
+    /// Size of the first big block of walls found
+    fn big_wall_block(&self) -> Option<(usize, usize)> {
+        let has_block = |x: usize, y: usize, (width, height): (usize, usize)| {
+            x + width <= self.width()
+                && y + height <= self.height()
+                && (0..width).all(|dx| (0..height).all(|dy| self.is_wall(x + dx, y + dy)))
+        };
+
+        self.iter().find_map(|cell| {
+            [(3, 3), (2, 4), (4, 2)]
+                .into_iter()
+                .find(|&size| has_block(cell.x(), cell.y(), size))
+        })
+    }
+
+    /// The level as an unsolved puzzle, with wall counts as headers and only monsters and
+    /// treasures shown. This is the format read by `Solver::try_from`.
+    pub fn to_puzzle_string(&self) -> String {
+        let mut puzzle = Puzzle::from_level(self);
+        puzzle.solution = None;
+        puzzle.to_string()
+    }
 }
 
 impl From<GenLevel> for Level {
@@ -131,6 +233,7 @@ pub struct LevelBuilder {
     height: usize,
     check_unique_solution: bool,
     check_too_many_walls: bool,
+    seed: Option<u64>,
 }
 
 impl LevelBuilder {
@@ -140,9 +243,16 @@ impl LevelBuilder {
             height,
             check_unique_solution: false,
             check_too_many_walls: false,
+            seed: None,
         }
     }
 
+    /// Generate from a fixed seed, so the same builder always gives the same level
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
     pub fn check_unique_solution(mut self) -> Self {
         self.check_unique_solution = true;
         self
@@ -154,89 +264,174 @@ impl LevelBuilder {
     }
 
     pub fn build(&self) -> Result<Level, &'static str> {
+        self.build_with_report().map(|(level, _)| level)
+    }
+
+    /// Same as [`LevelBuilder::build`], with a report of the attempts it took
+    pub fn build_with_report(&self) -> Result<(Level, GenerationReport), &'static str> {
         let start = chrono::Utc::now();
+        let mut report = GenerationReport::default();
+        let mut rng = self.seed.map_or_else(Rng::new, Rng::with_seed);
         let level = loop {
-            let level = Level::random(self.width, self.height)?;
-
-            if self.check_too_many_walls {
-                let has_too_many_walls = level.iter().any(|cell| {
-                    let x = cell.x();
-                    let y = cell.y();
-
-                    // Check for 3x3 wall blocks
-                    if x + 2 < level.width()
-                        && y + 2 < level.height()
-                        && (0..3).all(|dx| (0..3).all(|dy| level.is_wall(x + dx, y + dy)))
-                    {
-                        info!("Level has 3x3 wall blocks");
-                        return true;
-                    }
-
-                    // Check for 2x4 wall blocks
-                    if x + 1 < level.width()
-                        && y + 3 < level.height()
-                        && (0..2).all(|dx| (0..4).all(|dy| level.is_wall(x + dx, y + dy)))
-                    {
-                        info!("Level has 2x4 wall blocks");
-                        return true;
-                    }
-
-                    // Check for 4x2 wall blocks
-                    if x + 3 < level.width()
-                        && y + 1 < level.height()
-                        && (0..4).all(|dx| (0..2).all(|dy| level.is_wall(x + dx, y + dy)))
-                    {
-                        info!("Level has 4x2 wall blocks");
-                        return true;
-                    }
-
-                    false
-                });
-
-                if has_too_many_walls {
-                    continue;
-                } else {
-                    info!("Level has no big wall blocks");
-                }
-            }
-
-            if self.check_unique_solution {
-                let solver_start = chrono::Utc::now();
-                let solutions = Solver::from_level(&level).all_solutions();
-                if solutions.is_empty() {
-                    panic!("Generated level without solution:\n{:?}", level);
-                };
-                info!(
-                    "Solved level in {:?}",
-                    chrono::Utc::now()
-                        .signed_duration_since(solver_start)
-                        .to_std()
-                        .unwrap()
-                );
-
-                if solutions.len() == 1 {
-                    info!("Level has unique solution");
-                } else {
-                    info!("Level has multiple solutions");
-                    continue;
-                }
+            if let Some(level) = self.attempt_with_rng(&mut rng, &mut report)? {
+                break level;
             }
-
-            break level;
         };
         info!(
-            "Generated {} level in {:?}",
+            "Generated {} level in {:?} after {} attempts",
             match (self.check_unique_solution, self.check_too_many_walls) {
                 (true, true) => "validated",
                 (true, false) => "unique",
                 (false, true) => "filtered",
                 (false, false) => "random",
             },
-            chrono::Utc::now()
-                .signed_duration_since(start)
-                .to_std()
-                .unwrap()
+            elapsed_since(start),
+            report.attempts
         );
-        Ok(level)
+        Ok((level, report))
+    }
+
+    /// A single attempt of [`LevelBuilder::build_with_report`], generating the level of
+    /// `seed` and returning `None` when the checks reject it. The builder's own seed is
+    /// not used.
+    pub fn attempt(&self, seed: u64) -> Result<(Option<Level>, GenerationReport), &'static str> {
+        let mut report = GenerationReport::default();
+        let level = self.attempt_with_rng(&mut Rng::with_seed(seed), &mut report)?;
+        Ok((level, report))
+    }
+
+    fn attempt_with_rng(
+        &self,
+        rng: &mut Rng,
+        report: &mut GenerationReport,
+    ) -> Result<Option<Level>, &'static str> {
+        let generator_start = chrono::Utc::now();
+        // The rng state before the attempt gives the same level to `Level::random_with_seed`
+        let seed = rng.get_seed();
+        let level = Level::random_with_rng(self.width, self.height, rng)?;
+        report.attempts += 1;
+
+        if self.check_too_many_walls {
+            let big_wall_block = level.big_wall_block();
+            report.generating_time += elapsed_since(generator_start);
+            if let Some((width, height)) = big_wall_block {
+                info!("Level has {width}x{height} wall blocks");
+                report.rejected_big_wall_blocks += 1;
+                return Ok(None);
+            } else {
+                info!("Level has no big wall blocks");
+            }
+        } else {
+            report.generating_time += elapsed_since(generator_start);
+        }
+
+        if self.check_unique_solution {
+            let solver_start = chrono::Utc::now();
+            let (solutions, difficulty) = Solver::from_level(&level).solutions_and_difficulty();
+            if solutions.is_empty() {
+                panic!("Generated level without solution:\n{:?}", level);
+            };
+            let solving_time = elapsed_since(solver_start);
+            report.solving_time += solving_time;
+            info!("Solved level in {:?}", solving_time);
+
+            if solutions.len() == 1 {
+                info!("Level has unique solution");
+                report.difficulty = Some(difficulty);
+            } else {
+                info!("Level has multiple solutions");
+                report.rejected_not_unique += 1;
+                return Ok(None);
+            }
+        }
+
+        report.seed = seed;
+        Ok(Some(level))
+    }
+}
+
+/// What it took [`LevelBuilder::build_with_report`] to find a level
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct GenerationReport {
+    /// Levels generated, including the accepted one
+    pub attempts: usize,
+    /// Levels rejected for having big blocks of walls
+    pub rejected_big_wall_blocks: usize,
+    /// Levels rejected for having more than one solution
+    pub rejected_not_unique: usize,
+    /// Time spent generating levels and checking their walls
+    pub generating_time: Duration,
+    /// Time spent solving levels to check their solution is unique
+    pub solving_time: Duration,
+    /// The seed that gives the accepted level to [`Level::random_with_seed`]
+    pub seed: u64,
+    /// Rating of the accepted level, from the search checking its solution is unique
+    pub difficulty: Option<Difficulty>,
+}
+
+impl GenerationReport {
+    /// Adds the counts and times of another report, for totals over several builds. The
+    /// seed and difficulty are left as they are.
+    pub fn add(&mut self, other: &GenerationReport) {
+        self.attempts += other.attempts;
+        self.rejected_big_wall_blocks += other.rejected_big_wall_blocks;
+        self.rejected_not_unique += other.rejected_not_unique;
+        self.generating_time += other.generating_time;
+        self.solving_time += other.solving_time;
+    }
+}
+
+/// FNV-1a hash of the bytes. Unlike std's hashers it is the same on every platform and
+/// release, so it can make seeds and storage keys.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+fn elapsed_since(start: chrono::DateTime<chrono::Utc>) -> Duration {
+    chrono::Utc::now()
+        .signed_duration_since(start)
+        .to_std()
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    }
+
+    #[test]
+    fn test_build_with_report() {
+        let builder = Level::builder(8, 8)
+            .seed(7)
+            .check_too_many_walls()
+            .check_unique_solution();
+        let (level, report) = builder.build_with_report().unwrap();
+        assert!(level == builder.build().unwrap());
+        assert_eq!(
+            report.attempts,
+            report.rejected_big_wall_blocks + report.rejected_not_unique + 1
+        );
+        assert!(Level::random_with_seed(8, 8, report.seed).unwrap() == level);
+        let (attempt, attempt_report) = builder.attempt(report.seed).unwrap();
+        assert!(attempt.as_ref() == Some(&level));
+        assert_eq!(attempt_report.attempts, 1);
+        assert_eq!(attempt_report.difficulty, report.difficulty);
+        assert_eq!(
+            report.difficulty,
+            Some(Solver::from_level(&level).difficulty())
+        );
+
+        let (_, report) = Level::builder(6, 6).seed(3).build_with_report().unwrap();
+        assert_eq!(report.attempts, 1);
+        assert_eq!(report.seed, 3);
+        assert_eq!(report.solving_time, Duration::ZERO);
+        assert_eq!(report.difficulty, None);
     }
 }
@@ -0,0 +1,445 @@
+//! Puzzle packs: many puzzles in one text file, each with optional metadata and solution.
+//!
+//! Puzzles are separated by blank lines. Each puzzle starts with optional `key: value`
+//! metadata lines, followed by the puzzle in the format read by `Solver::try_from`, and
+//! optionally a `solution:` line followed by the solved level:
+//!
+//! ```text
+//! title: Tenaxxus's Gullet
+//! author: Zach Gage
+//! seed: 42
+//! difficulty: Hard
+//!   4 4 2 6 2 3 4 7
+//! 7 ? ? ? ? ? M ? ?
+//! 3 ? ? ? ? ? ? ? ?
+//! ...
+//! solution:
+//! #####M##
+//! ...#..##
+//! ...
+//! ```
+//!
+//! The known metadata keys are `title`, `author`, `seed` and `difficulty`. Puzzles only show
+//! monsters and treasures, so cells are `M`, `T` or `?`.
+
+use std::fmt::Display;
+
+use crate::{
+    solver::{parse_puzzle, SolverCell, SolverLevel},
+    Difficulty, Level, Solver,
+};
+
+/// A puzzle as shown to the player: wall counts, monsters and treasures
+#[derive(Clone, PartialEq)]
+pub struct Puzzle {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    /// Seed the puzzle was generated from
+    pub seed: Option<u64>,
+    pub difficulty: Option<Difficulty>,
+    pub solution: Option<Level>,
+    pub(crate) cells: SolverLevel,
+    row_walls: Vec<usize>,
+    col_walls: Vec<usize>,
+}
+
+impl Puzzle {
+    /// The puzzle for a level, with the level as its solution
+    pub fn from_level(level: &Level) -> Self {
+        let mut row_walls = vec![0; level.height()];
+        let mut col_walls = vec![0; level.width()];
+        for cell in level.iter().filter(|c| c.has_wall()) {
+            row_walls[cell.y()] += 1;
+            col_walls[cell.x()] += 1;
+        }
+
+        Puzzle {
+            title: None,
+            author: None,
+            seed: None,
+            difficulty: None,
+            solution: Some(level.clone()),
+            cells: SolverLevel::from(level),
+            row_walls,
+            col_walls,
+        }
+    }
+
+    /// A puzzle with the given wall counts, and monsters and treasures at `(x, y)` positions
+    pub fn new(
+        row_walls: Vec<usize>,
+        col_walls: Vec<usize>,
+        monsters: &[(usize, usize)],
+        treasures: &[(usize, usize)],
+    ) -> Result<Self, String> {
+        let (width, height) = (col_walls.len(), row_walls.len());
+        let mut cells = SolverLevel::new(width, height, SolverCell::Unknown);
+        for (&(x, y), cell) in monsters
+            .iter()
+            .map(|pos| (pos, SolverCell::Monster))
+            .chain(treasures.iter().map(|pos| (pos, SolverCell::Treasure)))
+        {
+            if x >= width || y >= height {
+                return Err(format!("Position ({x}, {y}) is outside the board"));
+            }
+            cells[(x, y).into()] = cell;
+        }
+
+        Ok(Puzzle {
+            title: None,
+            author: None,
+            seed: None,
+            difficulty: None,
+            solution: None,
+            cells,
+            row_walls,
+            col_walls,
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.height()
+    }
+
+    /// Number of walls in each row
+    pub fn row_walls(&self) -> &[usize] {
+        &self.row_walls
+    }
+
+    /// Number of walls in each column
+    pub fn col_walls(&self) -> &[usize] {
+        &self.col_walls
+    }
+
+    pub fn is_monster(&self, x: usize, y: usize) -> bool {
+        self.cells[(x, y).into()] == SolverCell::Monster
+    }
+
+    pub fn is_treasure(&self, x: usize, y: usize) -> bool {
+        self.cells[(x, y).into()] == SolverCell::Treasure
+    }
+
+    /// Positions of the monsters, row by row
+    pub fn monsters(&self) -> Vec<(usize, usize)> {
+        self.positions(SolverCell::Monster)
+    }
+
+    /// Positions of the treasures, row by row
+    pub fn treasures(&self) -> Vec<(usize, usize)> {
+        self.positions(SolverCell::Treasure)
+    }
+
+    fn positions(&self, kind: SolverCell) -> Vec<(usize, usize)> {
+        self.cells
+            .iter()
+            .filter(|(cell, _)| **cell == kind)
+            .map(|(_, pos)| (pos.x, pos.y))
+            .collect()
+    }
+
+    pub fn solver(&self) -> Solver {
+        Solver::from_parts(
+            self.cells.clone(),
+            self.row_walls.clone(),
+            self.col_walls.clone(),
+        )
+    }
+
+    /// The level to play: the stored solution, or the first one the solver finds
+    pub fn solved_level(&self) -> Option<Level> {
+        self.solution
+            .clone()
+            .or_else(|| self.solver().first_solution())
+    }
+
+    /// Whether both puzzles have the same headers, monsters and treasures
+    pub fn has_same_board(&self, other: &Puzzle) -> bool {
+        self.cells == other.cells
+            && self.row_walls == other.row_walls
+            && self.col_walls == other.col_walls
+    }
+
+    fn write_grid(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let header_width = self
+            .row_walls
+            .iter()
+            .map(|n| n.to_string().len())
+            .max()
+            .unwrap_or(1);
+
+        write!(f, "{}", " ".repeat(header_width))?;
+        for count in &self.col_walls {
+            write!(f, " {count}")?;
+        }
+        writeln!(f)?;
+        for (y, count) in self.row_walls.iter().enumerate() {
+            write!(f, "{count:>header_width$}")?;
+            for x in 0..self.width() {
+                write!(f, " {:?}", self.cells[(x, y).into()])?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Writes the puzzle in the pack format
+impl Display for Puzzle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(title) = &self.title {
+            writeln!(f, "title: {title}")?;
+        }
+        if let Some(author) = &self.author {
+            writeln!(f, "author: {author}")?;
+        }
+        if let Some(seed) = self.seed {
+            writeln!(f, "seed: {seed}")?;
+        }
+        if let Some(difficulty) = self.difficulty {
+            writeln!(f, "difficulty: {difficulty}")?;
+        }
+        self.write_grid(f)?;
+        if let Some(solution) = &self.solution {
+            writeln!(f, "solution:")?;
+            write!(f, "{solution:?}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads a single puzzle in the pack format
+impl TryFrom<&str> for Puzzle {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut title = None;
+        let mut author = None;
+        let mut seed = None;
+        let mut difficulty = None;
+
+        let mut lines = value.trim().lines().map(str::trim).peekable();
+        while let Some(line) = lines.next_if(|l| l.starts_with(char::is_alphabetic)) {
+            let Some((key, value)) = line.split_once(':') else {
+                return Err(format!("Expected `key: value`, got {line}"));
+            };
+            let value = value.trim();
+            match key.trim() {
+                "title" => title = Some(value.to_string()),
+                "author" => author = Some(value.to_string()),
+                "seed" => {
+                    seed = Some(
+                        value
+                            .parse::<u64>()
+                            .map_err(|_| format!("Invalid seed: {value}"))?,
+                    )
+                }
+                "difficulty" => difficulty = Some(value.parse::<Difficulty>()?),
+                key => return Err(format!("Unknown key: {key}")),
+            }
+        }
+
+        let grid = lines
+            .by_ref()
+            .take_while(|l| *l != "solution:")
+            .collect::<Vec<_>>()
+            .join("\n");
+        let (cells, row_walls, col_walls) = parse_puzzle(&grid)?;
+        if cells
+            .iter()
+            .any(|(c, _)| matches!(c, SolverCell::Wall | SolverCell::Hallway))
+        {
+            return Err("Puzzles can only show monsters and treasures".to_string());
+        }
+
+        let solution = lines.collect::<Vec<_>>().join("\n");
+        let solution = if solution.is_empty() {
+            None
+        } else {
+            let level = Level::try_from(solution.as_str())?;
+            Some(level)
+        };
+
+        let puzzle = Puzzle {
+            title,
+            author,
+            seed,
+            difficulty,
+            solution,
+            cells,
+            row_walls,
+            col_walls,
+        };
+        if let Some(solution) = &puzzle.solution {
+            if !Puzzle::from_level(solution).has_same_board(&puzzle) {
+                return Err("Solution does not match the puzzle".to_string());
+            }
+        }
+        Ok(puzzle)
+    }
+}
+
+/// Many puzzles, played in order
+#[derive(Clone, Default, PartialEq)]
+pub struct Pack {
+    pub puzzles: Vec<Puzzle>,
+}
+
+/// Writes the puzzles in the pack format, separated by blank lines
+impl Display for Pack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, puzzle) in self.puzzles.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{puzzle}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Pack {
+    /// Reads each puzzle of a pack on its own, so one bad puzzle doesn't hide the others
+    pub fn read_each(text: &str) -> Vec<Result<Puzzle, String>> {
+        split_entries(text)
+            .iter()
+            .map(|entry| Puzzle::try_from(entry.as_str()))
+            .collect()
+    }
+}
+
+impl TryFrom<&str> for Pack {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let puzzles = Pack::read_each(value)
+            .into_iter()
+            .enumerate()
+            .map(|(i, puzzle)| puzzle.map_err(|err| format!("Puzzle {}: {err}", i + 1)))
+            .collect::<Result<_, _>>()?;
+        Ok(Pack { puzzles })
+    }
+}
+
+fn split_entries(text: &str) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    for line in text.lines() {
+        if line.trim().is_empty() {
+            if !current.is_empty() {
+                entries.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push_str(line);
+            current.push('\n');
+        }
+    }
+    if !current.is_empty() {
+        entries.push(current);
+    }
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACK: &str = r#"
+title: Tenaxxus's Gullet
+author: Zach Gage
+difficulty: hard
+  4 4 2 6 2 3 4 7
+7 ? ? ? ? ? M ? ?
+3 ? ? ? ? ? ? ? ?
+4 ? T ? ? ? ? ? ?
+1 ? ? ? ? ? ? ? ?
+7 ? ? ? ? ? ? ? ?
+1 M ? ? ? ? ? ? ?
+6 ? ? ? ? ? ? ? ?
+3 ? ? M ? ? ? ? M
+
+seed: 7
+  3 2 2 5 4 2 4 5
+6 M ? M ? ? ? ? ?
+4 ? ? ? M ? ? ? ?
+4 ? ? ? ? ? ? T ?
+3 ? ? M ? ? ? ? ?
+3 M ? ? ? ? ? ? ?
+3 ? ? ? M ? ? ? M
+3 M ? ? ? ? ? ? ?
+1 ? ? ? ? ? ? ? M
+solution:
+M#M#####
+...M####
+#.##..T#
+#.M#...#
+M.##...#
+#..M#.#M
+M#.##...
+......#M
+"#;
+
+    #[test]
+    fn test_read_pack() {
+        let pack = Pack::try_from(PACK).unwrap();
+        assert_eq!(pack.puzzles.len(), 2);
+
+        let first = &pack.puzzles[0];
+        assert_eq!(first.title.as_deref(), Some("Tenaxxus's Gullet"));
+        assert_eq!(first.author.as_deref(), Some("Zach Gage"));
+        assert_eq!(first.difficulty, Some(Difficulty::Hard));
+        assert!(first.solution.is_none());
+        assert_eq!(first.row_walls()[0], 7);
+        assert!(first.is_treasure(1, 2));
+        assert!(first.solved_level().is_some());
+
+        let second = &pack.puzzles[1];
+        assert_eq!(second.seed, Some(7));
+        assert!(second.solution.is_some());
+    }
+
+    #[test]
+    fn test_write_pack() {
+        let pack = Pack::try_from(PACK).unwrap();
+        assert!(Pack::try_from(pack.to_string().as_str()).unwrap() == pack);
+
+        let level = Level::random_with_seed(10, 6, 3).unwrap();
+        let puzzle = Puzzle::from_level(&level);
+        assert!(Puzzle::try_from(puzzle.to_string().as_str()).unwrap() == puzzle);
+    }
+
+    #[test]
+    fn test_new_puzzle() {
+        let pack = Pack::try_from(PACK).unwrap();
+        let puzzle = &pack.puzzles[0];
+        let new = Puzzle::new(
+            puzzle.row_walls().to_vec(),
+            puzzle.col_walls().to_vec(),
+            &puzzle.monsters(),
+            &puzzle.treasures(),
+        )
+        .unwrap();
+        assert!(new.has_same_board(puzzle));
+        assert_eq!(new.treasures(), vec![(1, 2)]);
+
+        assert!(Puzzle::new(vec![0], vec![0], &[(1, 0)], &[]).is_err());
+    }
+
+    #[test]
+    fn test_read_each() {
+        let puzzles = Pack::read_each("1 0\n1 ? ?\n\n\n1 0\n1 # ?\n");
+        assert_eq!(puzzles.len(), 2);
+        assert!(puzzles[0].is_ok());
+        assert!(puzzles[1].is_err());
+    }
+
+    #[test]
+    fn test_invalid_pack() {
+        assert!(Pack::try_from("colour: red\n1 0\n1 ? ?").is_err());
+        assert!(Pack::try_from("1 0\n1 # ?").is_err());
+        assert!(Pack::try_from("1 0\n1 ? ?\nsolution:\n..").is_err());
+    }
+}
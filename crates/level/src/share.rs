@@ -0,0 +1,247 @@
+//! Short, URL-safe codes for sending a puzzle to someone.
+//!
+//! A code is base64url without padding, of a version byte, a form byte, the board
+//! dimensions, the form's data and a Fletcher-16 checksum. The full form holds the header
+//! counts and two bits per cell for monsters and treasures. The seed form only holds the
+//! seed, for puzzles that `Level::random_with_seed` gives back exactly.
+
+use crate::{solver::SolverCell, Level, Puzzle};
+
+const VERSION: u8 = 1;
+const FORM_FULL: u8 = 0;
+const FORM_SEED: u8 = 1;
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+impl Puzzle {
+    /// A code the puzzle can be read back from with [`Puzzle::from_share_code`]. Only the
+    /// puzzle is shared, not its title, author or difficulty.
+    ///
+    /// Panics for boards wider or taller than 255 cells.
+    pub fn to_share_code(&self) -> String {
+        let width = u8::try_from(self.width()).expect("Board too wide for a share code");
+        let height = u8::try_from(self.height()).expect("Board too tall for a share code");
+
+        let mut bytes = vec![VERSION];
+        match self.seed.filter(|seed| self.is_generated_from(*seed)) {
+            Some(seed) => {
+                bytes.extend([FORM_SEED, width, height]);
+                write_varint(&mut bytes, seed);
+            }
+            None => {
+                bytes.extend([FORM_FULL, width, height]);
+                // Counts can't be more than the width or height
+                bytes.extend(self.row_walls().iter().map(|&n| n as u8));
+                bytes.extend(self.col_walls().iter().map(|&n| n as u8));
+                let cells: Vec<u8> = (0..self.height())
+                    .flat_map(|y| (0..self.width()).map(move |x| (x, y)))
+                    .map(|(x, y)| match self.cells[(x, y).into()] {
+                        SolverCell::Monster => 1,
+                        SolverCell::Treasure => 2,
+                        _ => 0,
+                    })
+                    .collect();
+                bytes.extend(cells.chunks(4).map(|chunk| {
+                    chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0, |byte, (i, cell)| byte | (cell << (i * 2)))
+                }));
+            }
+        }
+        bytes.extend(checksum(&bytes).to_be_bytes());
+
+        encode_base64(&bytes)
+    }
+
+    /// Reads a puzzle from [`Puzzle::to_share_code`]. Puzzles from the seed form come
+    /// with their solution.
+    pub fn from_share_code(code: &str) -> Result<Self, String> {
+        let bytes = decode_base64(code.trim())?;
+        let Some((data, sum)) = bytes.split_last_chunk::<2>() else {
+            return Err("Share code is too short".to_string());
+        };
+        if checksum(data) != u16::from_be_bytes(*sum) {
+            return Err("Share code is damaged".to_string());
+        }
+
+        let mut reader = data.iter().copied();
+        let mut next = || reader.next().ok_or("Share code is too short".to_string());
+        let version = next()?;
+        if version != VERSION {
+            return Err(format!("Unsupported share code version {version}"));
+        }
+        let form = next()?;
+        let width = next()? as usize;
+        let height = next()? as usize;
+
+        match form {
+            FORM_SEED => {
+                let seed = read_varint(&mut next)?;
+                let level = Level::random_with_seed(width, height, seed)?;
+                let mut puzzle = Puzzle::from_level(&level);
+                puzzle.seed = Some(seed);
+                Ok(puzzle)
+            }
+            FORM_FULL => {
+                let row_walls = (0..height)
+                    .map(|_| next().map(usize::from))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let col_walls = (0..width)
+                    .map(|_| next().map(usize::from))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let packed = (0..(width * height).div_ceil(4))
+                    .map(|_| next())
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                let mut text = String::new();
+                for count in &col_walls {
+                    text += &format!(" {count}");
+                }
+                for (y, count) in row_walls.iter().enumerate() {
+                    text += &format!("\n{count}");
+                    for x in 0..width {
+                        let i = y * width + x;
+                        text += match (packed[i / 4] >> ((i % 4) * 2)) & 0b11 {
+                            0 => " ?",
+                            1 => " M",
+                            2 => " T",
+                            _ => return Err("Invalid cell in share code".to_string()),
+                        };
+                    }
+                }
+                Puzzle::try_from(text.as_str())
+            }
+            _ => Err(format!("Unknown share code form {form}")),
+        }
+    }
+
+    fn is_generated_from(&self, seed: u64) -> bool {
+        Level::random_with_seed(self.width(), self.height(), seed)
+            .is_ok_and(|level| Puzzle::from_level(&level).has_same_board(self))
+    }
+}
+
+fn checksum(bytes: &[u8]) -> u16 {
+    let (a, b) = bytes.iter().fold((0u16, 0u16), |(a, b), &byte| {
+        let a = (a + byte as u16) % 255;
+        (a, (b + a) % 255)
+    });
+    (b << 8) | a
+}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+fn read_varint(next: &mut impl FnMut() -> Result<u8, String>) -> Result<u64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = next()?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("Invalid seed in share code".to_string())
+}
+
+fn encode_base64(bytes: &[u8]) -> String {
+    let mut code = String::new();
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &byte)| n | ((byte as u32) << (16 - i * 8)));
+        for i in 0..=chunk.len() {
+            code.push(BASE64_ALPHABET[(n >> (18 - i * 6)) as usize & 0x3f] as char);
+        }
+    }
+    code
+}
+
+fn decode_base64(code: &str) -> Result<Vec<u8>, String> {
+    let values = code
+        .bytes()
+        .map(|c| {
+            BASE64_ALPHABET
+                .iter()
+                .position(|&a| a == c)
+                .map(|v| v as u32)
+                .ok_or_else(|| format!("Invalid character in share code: {}", c as char))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut bytes = Vec::new();
+    for chunk in values.chunks(4) {
+        if chunk.len() == 1 {
+            return Err("Share code has the wrong length".to_string());
+        }
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &v)| n | (v << (18 - i * 6)));
+        bytes.extend((0..chunk.len() - 1).map(|i| (n >> (16 - i * 8)) as u8));
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base64() {
+        for len in 0..10 {
+            let bytes: Vec<u8> = (0..len).map(|i: u32| (i * 37 + 200) as u8).collect();
+            assert_eq!(decode_base64(&encode_base64(&bytes)).unwrap(), bytes);
+        }
+        assert_eq!(encode_base64(b"Man"), "TWFu");
+        assert!(decode_base64("TWF+").is_err());
+    }
+
+    #[test]
+    fn test_full_share_code() {
+        let level = Level::random_with_seed(8, 8, 5).unwrap();
+        let mut puzzle = Puzzle::from_level(&level);
+        puzzle.solution = None;
+
+        let code = puzzle.to_share_code();
+        assert!(code.len() < 60, "Share code too long: {code}");
+        assert!(Puzzle::from_share_code(&code).unwrap() == puzzle);
+    }
+
+    #[test]
+    fn test_seed_share_code() {
+        let level = Level::random_with_seed(10, 7, 123456).unwrap();
+        let mut puzzle = Puzzle::from_level(&level);
+        puzzle.seed = Some(123456);
+
+        let code = puzzle.to_share_code();
+        assert!(code.len() < 16, "Share code too long: {code}");
+        assert!(Puzzle::from_share_code(&code).unwrap() == puzzle);
+
+        // A seed that doesn't give the puzzle is not used
+        puzzle.seed = Some(1);
+        let code = puzzle.to_share_code();
+        assert!(Puzzle::from_share_code(&code).unwrap().seed.is_none());
+    }
+
+    #[test]
+    fn test_damaged_share_code() {
+        let level = Level::random_with_seed(8, 8, 5).unwrap();
+        let code = Puzzle::from_level(&level).to_share_code();
+
+        let mut damaged = code.clone().into_bytes();
+        damaged[6] = if damaged[6] == b'A' { b'B' } else { b'A' };
+        let damaged = String::from_utf8(damaged).unwrap();
+        assert!(Puzzle::from_share_code(&damaged).is_err());
+        assert!(Puzzle::from_share_code(&code[..code.len() - 3]).is_err());
+        assert!(Puzzle::from_share_code("not a code!").is_err());
+    }
+}
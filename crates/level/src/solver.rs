@@ -1,13 +1,20 @@
-use bevy::utils::HashSet;
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
 use rayon::prelude::*;
 
 use crate::{
     grid::{Grid, GridPos},
-    Cell, CellFloor, CellKind, Level,
+    Board, Cell, CellFloor, CellKind, Contradiction, Difficulty, Hint, HintArea, Level, Mark,
 };
 
 #[derive(Clone, Copy, PartialEq, Eq)]
-enum SolverCell {
+pub(crate) enum SolverCell {
     Hallway,
     Wall,
     Unknown,
@@ -15,8 +22,6 @@ enum SolverCell {
     Treasure,
 }
 
-// TODO: Use [wasm-bindgen-rayon](https://github.com/RReverser/wasm-bindgen-rayon) for parallelism on wasm
-
 impl From<&Cell> for SolverCell {
     fn from(value: &Cell) -> Self {
         match value.kind {
@@ -40,7 +45,7 @@ impl std::fmt::Debug for SolverCell {
     }
 }
 
-type SolverLevel = Grid<SolverCell>;
+pub(crate) type SolverLevel = Grid<SolverCell>;
 
 impl From<&Level> for SolverLevel {
     fn from(level: &Level) -> Self {
@@ -98,66 +103,85 @@ impl TryFrom<&str> for SolverLevel {
     }
 }
 
+/// Reads a solved level, in the same format as its `Debug` output
+impl TryFrom<&str> for Level {
+    type Error = String;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let grid = SolverLevel::try_from(value)?;
+        if let Some((_, pos)) = grid.iter().find(|(c, _)| **c == SolverCell::Unknown) {
+            return Err(format!("Unknown cell at {:?}", pos));
+        }
+        Ok(Level::from(&grid))
+    }
+}
+
 impl TryFrom<&str> for Solver {
     type Error = String;
 
     fn try_from(value: &str) -> Result<Self, Self::Error> {
-        let mut lines = value.trim().lines();
-        let Some(col_header) = lines.next() else {
-            return Err("Column header not found".to_string());
+        let (grid, row_numbers, col_numbers) = parse_puzzle(value)?;
+        Ok(Solver::from_parts(grid, row_numbers, col_numbers))
+    }
+}
+
+/// Reads a puzzle with wall counts as headers, returning the cells and the row and column counts
+pub(crate) fn parse_puzzle(value: &str) -> Result<(SolverLevel, Vec<usize>, Vec<usize>), String> {
+    let mut lines = value.trim().lines();
+    let Some(col_header) = lines.next() else {
+        return Err("Column header not found".to_string());
+    };
+
+    let mut col_numbers: Vec<usize> = Vec::new();
+    for v in col_header.split_whitespace() {
+        let Ok(v) = v.parse::<usize>() else {
+            return Err(format!(
+                "Could not read column header value as integer: {v}"
+            ));
         };
+        col_numbers.push(v);
+    }
 
-        let mut col_numbers: Vec<usize> = Vec::new();
-        for v in col_header.split_whitespace() {
-            let Ok(v) = v.parse::<usize>() else {
-                return Err(format!(
-                    "Could not read column header value as integer: {v}"
-                ));
-            };
-            col_numbers.push(v);
+    let width = col_numbers.len();
+    let height = lines.clone().count();
+    let mut grid = Grid::new(width, height, SolverCell::Unknown);
+
+    let mut row_numbers: Vec<usize> = Vec::new();
+    for (y, line) in lines.enumerate() {
+        let mut line = line.split_whitespace();
+        if line.clone().count() != width + 1 {
+            return Err(format!(
+                "Invalid line length {} at line {}. Expected {}.",
+                line.clone().count(),
+                y,
+                width + 1
+            ));
         }
-
-        let width = col_numbers.len();
-        let height = lines.clone().count();
-        let mut grid = Grid::new(width, height, SolverCell::Unknown);
-
-        let mut row_numbers: Vec<usize> = Vec::new();
-        for (y, line) in lines.enumerate() {
-            let mut line = line.split_whitespace();
-            if line.clone().count() != width + 1 {
-                return Err(format!(
-                    "Invalid line length {} at line {}. Expected {}.",
-                    line.clone().count(),
-                    y,
-                    width + 1
-                ));
-            }
-            let Some(row_header) = line.next() else {
-                return Err(format!("Row header not found on line {}", y));
-            };
-            let Ok(row_header) = row_header.parse::<usize>() else {
-                return Err(format!(
-                    "Could not row header on line {y} as integer: {row_header}"
-                ));
+        let Some(row_header) = line.next() else {
+            return Err(format!("Row header not found on line {}", y));
+        };
+        let Ok(row_header) = row_header.parse::<usize>() else {
+            return Err(format!(
+                "Could not row header on line {y} as integer: {row_header}"
+            ));
+        };
+        row_numbers.push(row_header);
+        for (x, v) in line.enumerate() {
+            let pos = (x, y).into();
+            grid[pos] = match v {
+                "#" => SolverCell::Wall,
+                "." => SolverCell::Hallway,
+                "M" => SolverCell::Monster,
+                "T" => SolverCell::Treasure,
+                "?" => SolverCell::Unknown,
+                _ => {
+                    return Err(format!("Invalid value at {pos:?}: {v}"));
+                }
             };
-            row_numbers.push(row_header);
-            for (x, v) in line.enumerate() {
-                let pos = (x, y).into();
-                grid[pos] = match v {
-                    "#" => SolverCell::Wall,
-                    "." => SolverCell::Hallway,
-                    "M" => SolverCell::Monster,
-                    "T" => SolverCell::Treasure,
-                    "?" => SolverCell::Unknown,
-                    _ => {
-                        return Err(format!("Invalid value at {pos:?}: {v}"));
-                    }
-                };
-            }
         }
-
-        Ok(Solver::from_parts(grid, row_numbers, col_numbers))
     }
+
+    Ok((grid, row_numbers, col_numbers))
 }
 
 impl SolverLevel {
@@ -209,6 +233,7 @@ pub struct Solver {
     placed_treasure_rooms: Vec<GridPos>,
     next_pos: Option<GridPos>,
     islands: IslandTracker,
+    cancelled: Option<Arc<AtomicBool>>,
 }
 
 impl std::fmt::Debug for Solver {
@@ -265,7 +290,11 @@ impl Solver {
         Self::from_parts(level, row_numbers, col_numbers)
     }
 
-    fn from_parts(level: SolverLevel, row_numbers: Vec<usize>, col_numbers: Vec<usize>) -> Self {
+    pub(crate) fn from_parts(
+        level: SolverLevel,
+        row_numbers: Vec<usize>,
+        col_numbers: Vec<usize>,
+    ) -> Self {
         let treasures = level.find_treasures();
         let monsters = level.find_monsters();
 
@@ -296,7 +325,175 @@ impl Solver {
             placed_treasure_rooms: Vec::new(),
             unsatisfied_monsters: monsters,
             next_pos: Some((0, 0).into()),
+            cancelled: None,
+        }
+    }
+
+    /// Stop searching once `cancelled` is set, for example when the search takes too long.
+    /// Only the solutions found by then are returned.
+    pub fn cancel_on(mut self, cancelled: Arc<AtomicBool>) -> Self {
+        self.cancelled = Some(cancelled);
+        self
+    }
+
+    /// Set up a solver with the marks from the board already placed, and hallways in lines
+    /// that have all their walls. Fails if the marks break the wall counts.
+    fn from_board(board: &Board) -> Result<Self, ()> {
+        let mut solver = Self::from_level(board.level());
+
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                let pos = (x, y).into();
+                if solver.level[pos] != SolverCell::Unknown {
+                    continue;
+                }
+                match board.mark(x, y) {
+                    Mark::Wall => solver.put_wall(pos)?,
+                    Mark::Hallway => solver.put_hallway(pos),
+                    Mark::Empty => {}
+                }
+            }
+        }
+
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                let pos = (x, y).into();
+                if solver.level[pos] == SolverCell::Unknown
+                    && (solver.row_missing_walls[y] == 0 || solver.col_missing_walls[x] == 0)
+                {
+                    solver.put_hallway(pos);
+                }
+            }
+        }
+
+        Ok(solver)
+    }
+
+    pub(crate) fn is_solved(board: &Board) -> bool {
+        let mut filled = board.clone();
+        for y in 0..board.height() {
+            for x in 0..board.width() {
+                if board.mark(x, y) != Mark::Wall {
+                    filled.set_mark(x, y, Mark::Hallway);
+                }
+            }
         }
+
+        // With every cell placed, the search only has to check the rules
+        Self::from_board(&filled).is_ok_and(|solver| !solver.all_solutions().is_empty())
+    }
+
+    pub(crate) fn hint(board: &Board) -> Option<Hint> {
+        // Lines with more walls than their count
+        let counts = Self::from_level(board.level());
+        let is_wall = |x, y| board.mark(x, y) == Mark::Wall;
+        for y in 0..board.height() {
+            if (0..board.width()).filter(|&x| is_wall(x, y)).count() > counts.row_missing_walls[y] {
+                return Some(Hint::Mistake(Contradiction::Row(y)));
+            }
+        }
+        for x in 0..board.width() {
+            if (0..board.height()).filter(|&y| is_wall(x, y)).count() > counts.col_missing_walls[x]
+            {
+                return Some(Hint::Mistake(Contradiction::Column(x)));
+            }
+        }
+
+        let Ok(solver) = Self::from_board(board) else {
+            return Some(Hint::Mistake(Contradiction::Rules));
+        };
+
+        // Lines without room left for their walls
+        for y in 0..solver.level.height() {
+            if solver.row_missing_walls[y] > solver.row_unknown_count[y] {
+                return Some(Hint::Mistake(Contradiction::Row(y)));
+            }
+        }
+        for x in 0..solver.level.width() {
+            if solver.col_missing_walls[x] > solver.col_unknown_count[x] {
+                return Some(Hint::Mistake(Contradiction::Column(x)));
+            }
+        }
+
+        // Deducing from marks that no solution fits can lead anywhere, even when
+        // they still fit the wall counts
+        let mut logical = solver.clone();
+        if logical.fill_out_logical_values().is_err() {
+            return Some(Hint::Mistake(Contradiction::Rules));
+        }
+        let solutions = solver.clone().all_solutions();
+        if solutions.is_empty() {
+            return Some(Hint::Mistake(Contradiction::Rules));
+        }
+
+        // Lines where the remaining cells must all be walls
+        for y in 0..solver.level.height() {
+            let missing_walls = solver.row_missing_walls[y];
+            if missing_walls > 0 && missing_walls == solver.row_unknown_count[y] {
+                let x = (0..solver.level.width())
+                    .find(|&x| solver.level[(x, y).into()] == SolverCell::Unknown)
+                    .unwrap();
+                return Some(Hint::Deduction {
+                    area: HintArea::Row(y),
+                    x,
+                    y,
+                    mark: Mark::Wall,
+                });
+            }
+        }
+        for x in 0..solver.level.width() {
+            let missing_walls = solver.col_missing_walls[x];
+            if missing_walls > 0 && missing_walls == solver.col_unknown_count[x] {
+                let y = (0..solver.level.height())
+                    .find(|&y| solver.level[(x, y).into()] == SolverCell::Unknown)
+                    .unwrap();
+                return Some(Hint::Deduction {
+                    area: HintArea::Column(x),
+                    x,
+                    y,
+                    mark: Mark::Wall,
+                });
+            }
+        }
+
+        // Cells following from the monster rules
+        if let Some((&cell, pos)) = logical
+            .level
+            .iter()
+            .find(|&(&cell, pos)| cell != solver.level[pos])
+        {
+            return Some(Hint::Deduction {
+                area: HintArea::Cell,
+                x: pos.x,
+                y: pos.y,
+                mark: if cell == SolverCell::Wall {
+                    Mark::Wall
+                } else {
+                    Mark::Hallway
+                },
+            });
+        }
+
+        // Cells that are the same in every solution
+        let hint = solver
+            .level
+            .iter()
+            .filter(|&(&cell, _)| cell == SolverCell::Unknown)
+            .find(|&(_, pos)| {
+                let is_wall = solutions[0].is_wall(pos.x, pos.y);
+                solutions.iter().all(|s| s.is_wall(pos.x, pos.y) == is_wall)
+            })
+            .map(|(_, pos)| Hint::Deduction {
+                area: HintArea::Cell,
+                x: pos.x,
+                y: pos.y,
+                mark: if solutions[0].is_wall(pos.x, pos.y) {
+                    Mark::Wall
+                } else {
+                    Mark::Hallway
+                },
+            });
+        hint
     }
 
     fn put_wall(&mut self, pos: GridPos) -> Result<(), ()> {
@@ -688,32 +885,71 @@ impl Solver {
         self.all_solutions().pop()
     }
 
-    pub fn all_solutions(mut self) -> Vec<Level> {
+    pub fn all_solutions(self) -> Vec<Level> {
+        self.search().solutions
+    }
+
+    /// Rate the level by how much guessing it takes to find all solutions
+    pub fn difficulty(self) -> Difficulty {
+        Difficulty::from_guesses(self.search().guesses)
+    }
+
+    /// All solutions and the difficulty rating, from a single search
+    pub fn solutions_and_difficulty(self) -> (Vec<Level>, Difficulty) {
+        let search = self.search();
+        (search.solutions, Difficulty::from_guesses(search.guesses))
+    }
+
+    fn search(mut self) -> Search {
+        if self
+            .cancelled
+            .as_ref()
+            .is_some_and(|cancelled| cancelled.load(Ordering::Relaxed))
+        {
+            return Search::default();
+        }
+
         if self.fill_out_logical_values().is_err() {
-            return Vec::new();
+            return Search::default();
         }
 
         if self.check_full_validity() {
-            return vec![Level::from(&self.level)];
+            return Search {
+                solutions: vec![Level::from(&self.level)],
+                guesses: 0,
+            };
         }
 
         if self.has_unmergable_islands() {
-            return Vec::new();
+            return Search::default();
         }
 
         if let Some(unhandled_treasure) = self.unhandled_treasures.pop() {
-            self.possible_treasure_rooms(unhandled_treasure)
-                .into_par_iter()
-                .map(|(room, exit)| {
-                    let mut solver = self.clone();
-                    if solver.place_treasure_room(room, exit).is_ok() {
-                        solver.all_solutions()
-                    } else {
-                        Vec::new()
-                    }
-                })
-                .flatten()
-                .collect::<Vec<_>>()
+            let possible_rooms = self.possible_treasure_rooms(unhandled_treasure);
+            let search = Search {
+                solutions: Vec::new(),
+                guesses: possible_rooms.len().saturating_sub(1),
+            };
+            let search_room = |(room, exit)| {
+                let mut solver = self.clone();
+                if solver.place_treasure_room(room, exit).is_ok() {
+                    solver.search()
+                } else {
+                    Search::default()
+                }
+            };
+            let results = if use_thread_pool() {
+                possible_rooms
+                    .into_par_iter()
+                    .map(search_room)
+                    .reduce(Search::default, Search::merge)
+            } else {
+                possible_rooms
+                    .into_iter()
+                    .map(search_room)
+                    .fold(Search::default(), Search::merge)
+            };
+            search.merge(results)
         } else {
             // TODO: Try smarter cell selection
 
@@ -721,49 +957,77 @@ impl Solver {
                 self.next_pos = self.level.next_pos(&pos);
 
                 if self.level[pos] == SolverCell::Unknown {
-                    let (wall_result, hallway_result) = rayon::join(
-                        || {
-                            let mut solver = self.clone();
-                            if solver.put_wall(pos).is_ok() {
-                                solver.all_solutions()
-                            } else {
-                                Vec::new()
-                            }
-                        },
-                        || {
-                            if pos.x == 0
-                                || pos.y == 0
-                                || !matches!(
-                                    (
-                                        self.level[(pos.x - 1, pos.y).into()],
-                                        self.level[(pos.x, pos.y - 1).into()],
-                                        self.level[(pos.x - 1, pos.y - 1).into()],
-                                    ),
-                                    (
-                                        SolverCell::Hallway,
-                                        SolverCell::Hallway,
-                                        SolverCell::Hallway
-                                    )
+                    let search_wall = || {
+                        let mut solver = self.clone();
+                        if solver.put_wall(pos).is_ok() {
+                            solver.search()
+                        } else {
+                            Search::default()
+                        }
+                    };
+                    let search_hallway = || {
+                        if pos.x == 0
+                            || pos.y == 0
+                            || !matches!(
+                                (
+                                    self.level[(pos.x - 1, pos.y).into()],
+                                    self.level[(pos.x, pos.y - 1).into()],
+                                    self.level[(pos.x - 1, pos.y - 1).into()],
+                                ),
+                                (
+                                    SolverCell::Hallway,
+                                    SolverCell::Hallway,
+                                    SolverCell::Hallway
                                 )
-                            {
-                                let mut solver = self.clone();
-                                solver.put_hallway(pos);
-                                solver.all_solutions()
-                            } else {
-                                Vec::new()
-                            }
-                        },
-                    );
+                            )
+                        {
+                            let mut solver = self.clone();
+                            solver.put_hallway(pos);
+                            solver.search()
+                        } else {
+                            Search::default()
+                        }
+                    };
+                    let (wall_result, hallway_result) = if use_thread_pool() {
+                        rayon::join(search_wall, search_hallway)
+                    } else {
+                        (search_wall(), search_hallway())
+                    };
 
-                    return [wall_result, hallway_result].concat();
+                    return Search {
+                        solutions: Vec::new(),
+                        guesses: 1,
+                    }
+                    .merge(wall_result)
+                    .merge(hallway_result);
                 }
             }
 
-            Vec::new()
+            Search::default()
         }
     }
 }
 
+/// Rayon blocks the calling thread while the pool works, which browsers don't allow on the
+/// main thread, so on wasm only searches that already run in the pool use it
+fn use_thread_pool() -> bool {
+    !cfg!(target_arch = "wasm32") || rayon::current_thread_index().is_some()
+}
+
+#[derive(Default)]
+struct Search {
+    solutions: Vec<Level>,
+    guesses: usize,
+}
+
+impl Search {
+    fn merge(mut self, other: Search) -> Search {
+        self.solutions.extend(other.solutions);
+        self.guesses += other.guesses;
+        self
+    }
+}
+
 #[derive(Clone, Copy)]
 struct IslandCell(Option<usize>);
 
@@ -877,6 +1141,35 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cancelled_search() {
+        let level = Level::random_with_seed(8, 8, 1).unwrap();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let solver = Solver::from_level(&level).cancel_on(cancelled.clone());
+        assert!(solver.clone().all_solutions().contains(&level));
+
+        cancelled.store(true, Ordering::Relaxed);
+        assert!(solver.all_solutions().is_empty());
+    }
+
+    #[test]
+    fn test_level_from_str() {
+        let level = Level::random(8, 8).unwrap();
+        let text = format!("{:?}", level);
+        assert!(Level::try_from(text.as_str()).unwrap() == level);
+
+        assert!(Level::try_from("M.#\n..?").is_err());
+    }
+
+    #[test]
+    fn test_puzzle_string() {
+        for seed in 0..20 {
+            let level = Level::random_with_seed(12, 10, seed).unwrap();
+            let solver = Solver::try_from(level.to_puzzle_string().as_str()).unwrap();
+            assert!(solver.all_solutions().contains(&level));
+        }
+    }
+
     #[test]
     fn test_solve_regression1() {
         let level = Level::from(
@@ -1044,6 +1337,57 @@ M......M
 
     // Source of levels: https://www.reddit.com/r/puzzles/comments/d72zg1/advanced_dungeons_and_diagrams_map_making_logic/
 
+    #[test]
+    fn test_difficulty() {
+        let easy = Solver::try_from(
+            r#"
+  4 4 2 6 2 3 4 7
+7 ? ? ? ? ? M ? ?
+3 ? ? ? ? ? ? ? ?
+4 ? T ? ? ? ? ? ?
+1 ? ? ? ? ? ? ? ?
+7 ? ? ? ? ? ? ? ?
+1 M ? ? ? ? ? ? ?
+6 ? ? ? ? ? ? ? ?
+3 ? ? M ? ? ? ? M
+"#,
+        )
+        .unwrap();
+        assert_eq!(easy.difficulty(), Difficulty::Easy);
+
+        let medium = Solver::try_from(
+            r#"
+  1 4 2 7 0 4 4 4
+3 ? ? ? ? ? ? ? ?
+2 ? ? ? ? ? ? ? M
+5 ? ? M ? ? ? ? ?
+3 ? ? ? ? ? ? ? M
+4 ? ? ? ? ? ? ? ?
+1 ? T ? ? ? ? ? M
+4 ? ? ? ? ? ? ? ?
+4 ? ? ? ? ? ? ? M
+"#,
+        )
+        .unwrap();
+        assert_eq!(medium.difficulty(), Difficulty::Medium);
+
+        let hard = Solver::try_from(
+            r#"
+  3 6 0 5 4 0 6 3
+6 ? ? M ? ? M ? ?
+2 M ? ? ? ? ? ? M
+4 ? ? ? ? ? ? ? ?
+3 ? ? ? ? M ? ? ?
+2 ? ? ? ? ? ? ? ?
+4 ? ? ? ? ? ? ? ?
+2 M ? ? ? ? ? ? M
+4 ? ? ? ? ? ? ? ?
+"#,
+        )
+        .unwrap();
+        assert_eq!(hard.difficulty(), Difficulty::Hard);
+    }
+
     #[test]
     fn test_solve_sample() {
         let solver = Solver::try_from(
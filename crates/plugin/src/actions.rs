@@ -0,0 +1,249 @@
+use bevy::{ecs::query::QueryFilter, prelude::*, utils::HashMap};
+
+use dnd_rs_level::Mark;
+
+use crate::{
+    events::CellChanged,
+    spawn_hallway_mark, spawn_question_mark, spawn_wall, stats,
+    theme::Theme,
+    toolbar::{ToolbarButton, ToolbarPressed},
+    AppState, Cell, Column, Floor, HallwayMark, QuestionMark, Row, Wall,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_event::<PlayerAction>()
+        .add_event::<UndoRequested>()
+        .init_resource::<History>()
+        .add_systems(OnEnter(AppState::Loading), reset_history)
+        .add_systems(
+            Update,
+            (
+                request_undo.run_if(not(stats::is_stats_page_open)),
+                apply_actions,
+            )
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        );
+}
+
+/// Changes made to the board by the player, undone together as a single step.
+/// Clicks, touches, keys and revealed hints all go through this event.
+#[derive(Event, Clone)]
+pub(crate) struct PlayerAction {
+    pub(crate) edits: Vec<CellEdit>,
+    /// Actions of the same drag stroke are undone together
+    pub(crate) stroke: Option<u32>,
+}
+
+impl PlayerAction {
+    pub(crate) fn single(x: usize, y: usize, change: Change) -> Self {
+        Self {
+            edits: vec![CellEdit { x, y, change }],
+            stroke: None,
+        }
+    }
+}
+
+#[derive(Event, Clone, Copy)]
+pub(crate) struct UndoRequested;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) struct CellEdit {
+    pub(crate) x: usize,
+    pub(crate) y: usize,
+    pub(crate) change: Change,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Change {
+    Mark(Mark),
+    QuestionMark(bool),
+}
+
+/// Edits that revert each player action, most recent last
+#[derive(Resource, Default)]
+pub(crate) struct History(Vec<(Option<u32>, Vec<CellEdit>)>);
+
+fn reset_history(mut history: ResMut<History>) {
+    history.0.clear();
+}
+
+// Z or backspace
+fn request_undo(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut toolbar_events: EventReader<ToolbarPressed>,
+    mut undo_requests: EventWriter<UndoRequested>,
+) {
+    let pressed = toolbar_events
+        .read()
+        .filter(|event| ***event == ToolbarButton::Undo)
+        .count()
+        > 0;
+    if pressed || keys.any_just_pressed([KeyCode::KeyZ, KeyCode::Backspace]) {
+        undo_requests.send(UndoRequested);
+    }
+}
+
+// The entities currently on the board, kept up to date while applying edits,
+// as spawns and despawns only show up in queries next frame
+struct BoardEntities {
+    marks: HashMap<(usize, usize), (Mark, Entity)>,
+    question_marks: HashMap<(usize, usize), Entity>,
+}
+
+impl BoardEntities {
+    // Returns the edit that reverts the change, if anything changed
+    fn apply(
+        &mut self,
+        commands: &mut Commands,
+        edit: CellEdit,
+        theme: &Theme,
+        q_floors: &Query<(&Transform, &Row, &Column), With<Floor>>,
+        q_cells: &Query<(&Transform, &Row, &Column), (With<Cell>, With<Sprite>)>,
+    ) -> Option<CellEdit> {
+        let pos = (edit.x, edit.y);
+
+        match edit.change {
+            Change::Mark(mark) => {
+                let (translation, row, column) = find_cell(q_floors, pos)?;
+
+                let previous = self.marks.get(&pos).map_or(Mark::Empty, |(mark, _)| *mark);
+                if previous == mark {
+                    return None;
+                }
+
+                if let Some((_, entity)) = self.marks.remove(&pos) {
+                    commands.entity(entity).despawn();
+                }
+                let entity = match mark {
+                    Mark::Wall => Some(spawn_wall(commands, theme, translation, row, column)),
+                    Mark::Hallway => Some(spawn_hallway_mark(
+                        commands,
+                        theme,
+                        translation,
+                        row,
+                        column,
+                    )),
+                    Mark::Empty => None,
+                };
+                if let Some(entity) = entity {
+                    self.marks.insert(pos, (mark, entity));
+                }
+
+                Some(CellEdit {
+                    change: Change::Mark(previous),
+                    ..edit
+                })
+            }
+            Change::QuestionMark(shown) => {
+                let (translation, row, column) = find_cell(q_cells, pos)?;
+
+                if self.question_marks.contains_key(&pos) == shown {
+                    return None;
+                }
+
+                if shown {
+                    let entity = spawn_question_mark(commands, theme, translation, row, column);
+                    self.question_marks.insert(pos, entity);
+                } else if let Some(entity) = self.question_marks.remove(&pos) {
+                    commands.entity(entity).despawn();
+                }
+
+                Some(CellEdit {
+                    change: Change::QuestionMark(!shown),
+                    ..edit
+                })
+            }
+        }
+    }
+}
+
+pub(crate) fn find_cell<F: QueryFilter>(
+    query: &Query<(&Transform, &Row, &Column), F>,
+    (x, y): (usize, usize),
+) -> Option<(Vec3, Row, Column)> {
+    query
+        .iter()
+        .find(|(_, row, column)| row.0 == y && column.0 == x)
+        .map(|(transform, row, column)| (transform.translation, *row, *column))
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn apply_actions(
+    mut commands: Commands,
+    mut actions: EventReader<PlayerAction>,
+    mut undo_requests: EventReader<UndoRequested>,
+    mut history: ResMut<History>,
+    mut cell_changes: EventWriter<CellChanged>,
+    theme: Res<Theme>,
+    q_floors: Query<(&Transform, &Row, &Column), With<Floor>>,
+    q_cells: Query<(&Transform, &Row, &Column), (With<Cell>, With<Sprite>)>,
+    q_walls: Query<(Entity, &Row, &Column), With<Wall>>,
+    q_hallway_marks: Query<(Entity, &Row, &Column), With<HallwayMark>>,
+    q_question_marks: Query<(Entity, &Row, &Column), With<QuestionMark>>,
+) {
+    if actions.is_empty() && undo_requests.is_empty() {
+        return;
+    }
+
+    let mut board = BoardEntities {
+        marks: q_walls
+            .iter()
+            .map(|(entity, row, column)| ((column.0, row.0), (Mark::Wall, entity)))
+            .chain(
+                q_hallway_marks
+                    .iter()
+                    .map(|(entity, row, column)| ((column.0, row.0), (Mark::Hallway, entity))),
+            )
+            .collect(),
+        question_marks: q_question_marks
+            .iter()
+            .map(|(entity, row, column)| ((column.0, row.0), entity))
+            .collect(),
+    };
+
+    for action in actions.read() {
+        let mut undo: Vec<CellEdit> = action
+            .edits
+            .iter()
+            .filter_map(|edit| {
+                let undo = board.apply(&mut commands, *edit, &theme, &q_floors, &q_cells)?;
+                send_cell_change(&mut cell_changes, *edit, undo);
+                Some(undo)
+            })
+            .collect();
+        if undo.is_empty() {
+            continue;
+        }
+
+        match history.0.last_mut() {
+            Some((stroke, edits)) if stroke.is_some() && *stroke == action.stroke => {
+                edits.append(&mut undo);
+            }
+            _ => history.0.push((action.stroke, undo)),
+        }
+    }
+
+    for _ in undo_requests.read() {
+        let Some((_, undo)) = history.0.pop() else {
+            continue;
+        };
+        for edit in undo.into_iter().rev() {
+            if let Some(redo) = board.apply(&mut commands, edit, &theme, &q_floors, &q_cells) {
+                send_cell_change(&mut cell_changes, edit, redo);
+            }
+        }
+    }
+}
+
+// `edit` was applied, and `undo` reverts it
+fn send_cell_change(events: &mut EventWriter<CellChanged>, edit: CellEdit, undo: CellEdit) {
+    if let (Change::Mark(to), Change::Mark(from)) = (edit.change, undo.change) {
+        events.send(CellChanged {
+            x: edit.x,
+            y: edit.y,
+            from,
+            to,
+        });
+    }
+}
@@ -0,0 +1,185 @@
+use bevy::prelude::*;
+
+use dnd_rs_level::Mark;
+
+use crate::{
+    actions::{self, CellEdit, Change, PlayerAction},
+    camera,
+    events::CellChanged,
+    is_cursor_in_cell,
+    paint::PlayerMarks,
+    stats,
+    toolbar::{self, ToolbarButton, ToolbarMessage, ToolbarPressed},
+    AppState, BoardPointer, Column, Floor, HeaderText, Row,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<AutoFill>().add_systems(
+        Update,
+        (
+            toggle_auto_fill,
+            handle_header_clicks
+                .run_if(not(camera::is_panning))
+                .run_if(not(toolbar::is_pointer_over_toolbar))
+                .run_if(not(stats::is_stats_page_open)),
+            auto_fill,
+        )
+            .before(actions::apply_actions)
+            .run_if(in_state(AppState::Playing)),
+    );
+}
+
+/// Fill rows and columns as soon as there is only one way to finish them
+#[derive(Resource, Default, Clone, Copy)]
+pub(crate) struct AutoFill(pub(crate) bool);
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Line {
+    Row(usize),
+    Column(usize),
+}
+
+impl Line {
+    fn contains(&self, row: &Row, column: &Column) -> bool {
+        match self {
+            Line::Row(y) => row.0 == *y,
+            Line::Column(x) => column.0 == *x,
+        }
+    }
+}
+
+fn toggle_auto_fill(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut toolbar_events: EventReader<ToolbarPressed>,
+    mut auto_fill: ResMut<AutoFill>,
+    mut q_message: Query<&mut Text, With<ToolbarMessage>>,
+) {
+    let pressed = toolbar_events
+        .read()
+        .filter(|event| ***event == ToolbarButton::AutoFill)
+        .count()
+        > 0;
+    if !pressed && !keys.just_pressed(KeyCode::KeyF) {
+        return;
+    }
+
+    auto_fill.0 = !auto_fill.0;
+    for mut text in &mut q_message {
+        text.sections[0].value = if auto_fill.0 {
+            "Auto-fill on".to_string()
+        } else {
+            "Auto-fill off".to_string()
+        };
+    }
+}
+
+fn handle_header_clicks(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    mut actions: EventWriter<PlayerAction>,
+    marks: PlayerMarks,
+    q_headers: Query<(&Transform, &HeaderText, Option<&Row>, Option<&Column>)>,
+    q_floors: Query<(&Row, &Column), With<Floor>>,
+    pointer: BoardPointer,
+) {
+    let position = if mouse_buttons.just_pressed(MouseButton::Left) {
+        pointer.cursor_position()
+    } else if let Some(touch) = touches.iter_just_pressed().next() {
+        pointer.viewport_to_board(touch.position())
+    } else {
+        return;
+    };
+    let Some(position) = position else {
+        return;
+    };
+
+    for (transform, header, row, column) in &q_headers {
+        if !is_cursor_in_cell(position, transform) {
+            continue;
+        }
+        let line = match (row, column) {
+            (Some(row), _) => Line::Row(row.0),
+            (_, Some(column)) => Line::Column(column.0),
+            _ => continue,
+        };
+        if let Some(action) = fill_line(line, header.0, &marks, &q_floors) {
+            actions.send(action);
+        }
+    }
+}
+
+// Board changes are sent when they are applied, so they are in place the frame after
+fn auto_fill(
+    mut cell_changes: EventReader<CellChanged>,
+    auto_fill: Res<AutoFill>,
+    mut actions: EventWriter<PlayerAction>,
+    marks: PlayerMarks,
+    q_headers: Query<(&HeaderText, Option<&Row>, Option<&Column>)>,
+    q_floors: Query<(&Row, &Column), With<Floor>>,
+) {
+    let changed = cell_changes.read().count() > 0;
+    if !auto_fill.0 || !changed {
+        return;
+    }
+
+    // One line at a time, filling it triggers the next
+    let action = q_headers.iter().find_map(|(header, row, column)| {
+        let line = match (row, column) {
+            (Some(row), _) => Line::Row(row.0),
+            (_, Some(column)) => Line::Column(column.0),
+            _ => return None,
+        };
+        fill_line(line, header.0, &marks, &q_floors)
+    });
+    if let Some(action) = action {
+        actions.send(action);
+    }
+}
+
+// Satisfied lines are filled with hallway marks, and lines with exactly as many
+// unmarked cells as missing walls are filled with walls
+fn fill_line(
+    line: Line,
+    expected_walls: usize,
+    marks: &PlayerMarks,
+    q_floors: &Query<(&Row, &Column), With<Floor>>,
+) -> Option<PlayerAction> {
+    let cells: Vec<(usize, usize)> = q_floors
+        .iter()
+        .filter(|(row, column)| line.contains(row, column))
+        .map(|(row, column)| (column.0, row.0))
+        .collect();
+
+    let walls = cells
+        .iter()
+        .filter(|&&cell| marks.mark(cell) == Mark::Wall)
+        .count();
+    let unmarked: Vec<(usize, usize)> = cells
+        .into_iter()
+        .filter(|&cell| marks.mark(cell) == Mark::Empty)
+        .collect();
+
+    let mark = if walls == expected_walls {
+        Mark::Hallway
+    } else if walls + unmarked.len() == expected_walls {
+        Mark::Wall
+    } else {
+        return None;
+    };
+
+    if unmarked.is_empty() {
+        return None;
+    }
+
+    Some(PlayerAction {
+        edits: unmarked
+            .into_iter()
+            .map(|(x, y)| CellEdit {
+                x,
+                y,
+                change: Change::Mark(mark),
+            })
+            .collect(),
+        stroke: None,
+    })
+}
@@ -0,0 +1,241 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    prelude::*,
+    render::camera::CameraUpdateSystem,
+    transform::TransformSystem,
+    window::PrimaryWindow,
+};
+
+use dnd_rs_level::Level;
+
+use crate::{
+    config, stats, AppState, BoardPointer, Column, HeaderText, MainCamera, Row, OFFSET,
+    PADDING_BOTTOM, PADDING_LEFT, PADDING_RIGHT, PADDING_TOP, UNIT_SIZE,
+};
+
+/// Smallest projection scale, a scale of 1 shows the whole board
+const MAX_ZOOM_SCALE: f32 = 0.2;
+const ZOOM_STEP: f32 = 1.25;
+// Scroll pixels that make one zoom step on touchpads
+const PIXELS_PER_ZOOM_STEP: f32 = 100.0;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(
+        OnEnter(AppState::Won),
+        reset_zoom.run_if(config::has_camera),
+    )
+    .add_systems(
+        Update,
+        (
+            zoom_with_mouse_wheel,
+            zoom_with_keys,
+            pan_with_mouse,
+            pan_and_zoom_with_touch,
+        )
+            .run_if(config::has_camera)
+            .run_if(in_state(AppState::Playing))
+            .run_if(not(stats::is_stats_page_open)),
+    )
+    .add_systems(
+        PostUpdate,
+        pin_headers
+            .after(CameraUpdateSystem)
+            .before(TransformSystem::TransformPropagate)
+            .run_if(config::has_camera)
+            .run_if(not(in_state(AppState::Loading))),
+    );
+}
+
+/// Dragging with ctrl held or with two fingers moves the camera instead of painting
+pub(crate) fn is_panning(keys: Res<ButtonInput<KeyCode>>, touches: Res<Touches>) -> bool {
+    keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) || touches.iter().count() > 1
+}
+
+type CameraQuery<'w, 's> =
+    Query<'w, 's, (&'static mut Transform, &'static mut OrthographicProjection), With<MainCamera>>;
+
+// Size of the board including headers and padding, the area shown when zoomed out
+fn board_size(level: &Level) -> Vec2 {
+    Vec2::new(
+        (level.width() as f32 + 1.0) * UNIT_SIZE + PADDING_LEFT + PADDING_RIGHT,
+        (level.height() as f32 + 1.0) * UNIT_SIZE + PADDING_TOP + PADDING_BOTTOM,
+    )
+}
+
+// Zooms by a factor, keeping the board position `focus` in place
+fn zoom(camera: &mut CameraQuery, level: &Level, factor: f32, focus: Option<Vec2>) {
+    let (mut transform, mut projection) = camera.single_mut();
+
+    let scale = (projection.scale * factor).clamp(MAX_ZOOM_SCALE, 1.0);
+    let factor = scale / projection.scale;
+    projection.scale = scale;
+
+    if let Some(focus) = focus {
+        let center = transform.translation.xy();
+        let center = focus - (focus - center) * factor;
+        transform.translation = center.extend(transform.translation.z);
+    }
+    clamp_to_board(&mut transform, &projection, level);
+}
+
+// Moves the camera so the board position under `from` ends up under `to`, and
+// returns how far it moved
+fn pan(camera: &mut CameraQuery, level: &Level, from: Vec2, to: Vec2) -> Vec2 {
+    let (mut transform, projection) = camera.single_mut();
+    let start = transform.translation.xy();
+    transform.translation += (from - to).extend(0.0);
+    clamp_to_board(&mut transform, &projection, level);
+    transform.translation.xy() - start
+}
+
+// Keeps the board filling the view. At a scale of 1 the camera is centered on the board.
+fn clamp_to_board(transform: &mut Transform, projection: &OrthographicProjection, level: &Level) {
+    let size = board_size(level);
+    let center = size / 2.0;
+    let reach = size * (1.0 - projection.scale) / 2.0;
+    let position = transform
+        .translation
+        .xy()
+        .clamp(center - reach, center + reach);
+    transform.translation = position.extend(transform.translation.z);
+}
+
+fn zoom_with_mouse_wheel(
+    mut wheel_events: EventReader<MouseWheel>,
+    mut q_camera: CameraQuery,
+    pointer: BoardPointer,
+    q_level: Query<&Level>,
+) {
+    let steps: f32 = wheel_events
+        .read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_ZOOM_STEP,
+        })
+        .sum();
+    if steps == 0.0 {
+        return;
+    }
+
+    zoom(
+        &mut q_camera,
+        q_level.single(),
+        ZOOM_STEP.powf(-steps),
+        pointer.cursor_position(),
+    );
+}
+
+// Plus and minus zoom around the center of the view, home shows the whole board
+fn zoom_with_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut q_camera: CameraQuery,
+    q_level: Query<&Level>,
+) {
+    let factor = if keys.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        1.0 / ZOOM_STEP
+    } else if keys.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        ZOOM_STEP
+    } else if keys.any_just_pressed([KeyCode::Home, KeyCode::Numpad0]) {
+        f32::INFINITY
+    } else {
+        return;
+    };
+    zoom(&mut q_camera, q_level.single(), factor, None);
+}
+
+fn pan_with_mouse(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut last_position: Local<Option<Vec2>>,
+    mut q_camera: CameraQuery,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    pointer: BoardPointer,
+    q_level: Query<&Level>,
+) {
+    let dragging = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+        && mouse_buttons.pressed(MouseButton::Left);
+    let position = q_windows
+        .get_single()
+        .ok()
+        .and_then(Window::cursor_position)
+        .filter(|_| dragging);
+
+    let from = last_position.and_then(|p| pointer.viewport_to_board(p));
+    let to = position.and_then(|p| pointer.viewport_to_board(p));
+    if let (Some(from), Some(to)) = (from, to) {
+        pan(&mut q_camera, q_level.single(), from, to);
+    }
+    *last_position = position;
+}
+
+// Shows the whole board for the confetti
+fn reset_zoom(mut q_camera: CameraQuery, q_level: Query<&Level>) {
+    zoom(&mut q_camera, q_level.single(), f32::INFINITY, None);
+}
+
+// Two fingers pan, and pinching zooms
+fn pan_and_zoom_with_touch(
+    touches: Res<Touches>,
+    mut q_camera: CameraQuery,
+    pointer: BoardPointer,
+    q_level: Query<&Level>,
+) {
+    let mut pressed = touches.iter();
+    let (Some(first), Some(second), None) = (pressed.next(), pressed.next(), pressed.next()) else {
+        return;
+    };
+    let level = q_level.single();
+
+    let previous_center = (first.previous_position() + second.previous_position()) / 2.0;
+    let center = (first.position() + second.position()) / 2.0;
+    let (Some(previous_center), Some(center)) = (
+        pointer.viewport_to_board(previous_center),
+        pointer.viewport_to_board(center),
+    ) else {
+        return;
+    };
+    // The board position under the fingers moves with the camera, and the pinch
+    // zooms around where it is after the pan
+    let focus = center + pan(&mut q_camera, level, previous_center, center);
+
+    let previous_distance = first
+        .previous_position()
+        .distance(second.previous_position());
+    let distance = first.position().distance(second.position());
+    if previous_distance > 0.0 && distance > 0.0 {
+        zoom(
+            &mut q_camera,
+            level,
+            previous_distance / distance,
+            Some(focus),
+        );
+    }
+}
+
+// Headers stay at the top and left edges of the view when scrolled past, so the
+// counts remain visible
+fn pin_headers(
+    q_camera: Query<(&Transform, &OrthographicProjection), With<MainCamera>>,
+    mut q_headers: Query<
+        (&mut Transform, Option<&Row>, Option<&Column>),
+        (With<HeaderText>, Without<MainCamera>),
+    >,
+    q_level: Query<&Level>,
+) {
+    let (camera_transform, projection) = q_camera.single();
+    let top = camera_transform.translation.y + projection.area.max.y;
+    let left = camera_transform.translation.x + projection.area.min.x;
+
+    let height = board_size(q_level.single()).y;
+    let column_header_y = height - (OFFSET + PADDING_TOP);
+    let row_header_x = OFFSET + PADDING_LEFT;
+
+    for (mut transform, row, column) in &mut q_headers {
+        if row.is_some() {
+            transform.translation.x = row_header_x.max(left + OFFSET);
+        }
+        if column.is_some() {
+            transform.translation.y = column_header_y.min(top - OFFSET);
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use bevy::{prelude::*, utils::HashSet};
+
+use dnd_rs_level::{Level, Mark};
+
+use crate::{
+    current_board,
+    theme::Theme,
+    toolbar::{ToolbarButton, ToolbarMessage, ToolbarPressed},
+    AppState, Cell, Column, GameComponent, HallwayMark, Row, Wall, CELL_SIZE,
+};
+
+const MISTAKE_FLASH_SECONDS: f32 = 1.5;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<AutoCheck>()
+        .init_resource::<Mistakes>()
+        .add_systems(OnEnter(AppState::Loading), reset_mistakes)
+        .add_systems(OnExit(AppState::Loading), spawn_mistake_indicators)
+        .add_systems(
+            Update,
+            (toggle_auto_check, update_mistake_indicators)
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        );
+}
+
+/// Mark mistakes as soon as they are made, instead of only when checking the board
+#[derive(Resource, Default, Clone, Copy)]
+pub(crate) struct AutoCheck(pub(crate) bool);
+
+/// Every wrong mark found while playing the current level
+#[derive(Resource, Default)]
+pub(crate) struct Mistakes(pub(crate) HashSet<(usize, usize, Mark)>);
+
+impl Mistakes {
+    pub(crate) fn count(&self) -> usize {
+        self.0.len()
+    }
+}
+
+#[derive(Component)]
+struct MistakeIndicator {
+    flash: Option<Timer>,
+}
+
+fn reset_mistakes(mut mistakes: ResMut<Mistakes>) {
+    mistakes.0.clear();
+}
+
+fn spawn_mistake_indicators(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    q_cells: Query<(&Transform, &Row, &Column), (With<Cell>, With<Sprite>)>,
+) {
+    for (transform, row, column) in &q_cells {
+        commands.spawn((
+            GameComponent,
+            MistakeIndicator { flash: None },
+            SpriteBundle {
+                transform: Transform {
+                    translation: transform.translation.with_z(1.5),
+                    scale: CELL_SIZE.extend(0.0),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    color: theme.mistake,
+                    ..Default::default()
+                },
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            *row,
+            *column,
+        ));
+    }
+}
+
+fn toggle_auto_check(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut toolbar_events: EventReader<ToolbarPressed>,
+    mut auto_check: ResMut<AutoCheck>,
+    mut q_message: Query<&mut Text, With<ToolbarMessage>>,
+) {
+    let pressed = toolbar_events
+        .read()
+        .filter(|event| ***event == ToolbarButton::AutoCheck)
+        .count()
+        > 0;
+    // Shift+C, as plain C checks the board once
+    let shortcut = keys.just_pressed(KeyCode::KeyC) && is_shift_pressed(&keys);
+    if !pressed && !shortcut {
+        return;
+    }
+
+    auto_check.0 = !auto_check.0;
+    for mut text in &mut q_message {
+        text.sections[0].value = if auto_check.0 {
+            "Auto-check on".to_string()
+        } else {
+            "Auto-check off".to_string()
+        };
+    }
+}
+
+fn is_shift_pressed(keys: &ButtonInput<KeyCode>) -> bool {
+    keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_mistake_indicators(
+    keys: Res<ButtonInput<KeyCode>>,
+    time: Res<Time>,
+    theme: Res<Theme>,
+    mut toolbar_events: EventReader<ToolbarPressed>,
+    auto_check: Res<AutoCheck>,
+    mut mistakes: ResMut<Mistakes>,
+    q_walls: Query<(&Row, &Column), With<Wall>>,
+    q_hallway_marks: Query<(&Row, &Column), With<HallwayMark>>,
+    q_level: Query<&Level>,
+    mut q_indicators: Query<(
+        &Row,
+        &Column,
+        &mut MistakeIndicator,
+        &mut Sprite,
+        &mut Visibility,
+    )>,
+    mut q_message: Query<&mut Text, With<ToolbarMessage>>,
+) {
+    let check_requested = toolbar_events
+        .read()
+        .filter(|event| ***event == ToolbarButton::Check)
+        .count()
+        > 0
+        || (keys.just_pressed(KeyCode::KeyC) && !is_shift_pressed(&keys));
+
+    let level = q_level.single();
+    let board = current_board(level, &q_walls, &q_hallway_marks);
+    let current_mistakes = board.mistakes();
+
+    if check_requested || auto_check.0 {
+        for &(x, y) in &current_mistakes {
+            mistakes.0.insert((x, y, board.mark(x, y)));
+        }
+    }
+
+    if check_requested {
+        for mut text in &mut q_message {
+            text.sections[0].value = match current_mistakes.len() {
+                0 => "No mistakes so far".to_string(),
+                1 => "1 mistake".to_string(),
+                n => format!("{n} mistakes"),
+            };
+        }
+    }
+
+    for (row, column, mut indicator, mut sprite, mut visibility) in &mut q_indicators {
+        let is_mistake = current_mistakes.contains(&(column.0, row.0));
+
+        if check_requested && is_mistake {
+            indicator.flash = Some(Timer::from_seconds(MISTAKE_FLASH_SECONDS, TimerMode::Once));
+        }
+        if let Some(flash) = &mut indicator.flash {
+            flash.tick(time.delta());
+        }
+
+        let alpha = match &indicator.flash {
+            _ if auto_check.0 && is_mistake => 1.0,
+            Some(flash) if !flash.finished() => flash.fraction_remaining(),
+            _ => 0.0,
+        };
+
+        if alpha > 0.0 {
+            sprite.color = theme.mistake.with_alpha(theme.mistake.alpha() * alpha);
+            *visibility = Visibility::Inherited;
+        } else {
+            indicator.flash = None;
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
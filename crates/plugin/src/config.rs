@@ -0,0 +1,84 @@
+use std::sync::Arc;
+
+use bevy::prelude::*;
+
+use dnd_rs_level::{Level, Pack, Puzzle};
+
+const MONSTER_COUNT: u32 = 40;
+
+/// Where the levels played come from. Daily levels are played regardless of the source.
+/// Saved progress is resumed with random levels, or when it is for the fixed level or a
+/// puzzle of the pack.
+#[derive(Clone)]
+pub enum LevelSource {
+    /// Random levels with a board size picked from the window dimensions
+    FitWindow,
+    /// Random levels of a fixed size
+    Random { width: usize, height: usize },
+    /// The same level every game
+    Fixed(Level),
+    /// The puzzles of a pack, in order
+    Pack(Pack),
+    /// Levels from a function, called for every new game
+    Custom(Arc<dyn Fn() -> Level + Send + Sync>),
+}
+
+/// Paths of the images on the board, relative to the asset folder
+#[derive(Clone, Debug)]
+pub struct AssetPaths {
+    pub treasure: String,
+    /// Each monster is drawn with an image picked at random from this list
+    pub monsters: Vec<String>,
+}
+
+impl Default for AssetPaths {
+    fn default() -> Self {
+        Self {
+            treasure: "treasure.png".to_string(),
+            monsters: (1..=MONSTER_COUNT)
+                .map(|i| format!("monsters/{i}.png"))
+                .collect(),
+        }
+    }
+}
+
+#[derive(Resource, Clone)]
+pub(crate) struct Config {
+    pub(crate) level_source: LevelSource,
+    pub(crate) assets: AssetPaths,
+    /// Spawn a camera showing the board, with zoom, pan and window layout
+    pub(crate) camera: bool,
+    /// Show confetti and a summary when the level is solved
+    pub(crate) win_screen: bool,
+    /// Placement of the board in the world. Cells are `UNIT_SIZE` apart before scaling.
+    pub(crate) transform: Transform,
+    /// Load and save progress, statistics and settings
+    pub(crate) persistence: bool,
+    /// Played first, before the level source and resumed progress
+    pub(crate) shared_puzzle: Option<Puzzle>,
+    /// Random levels to generate ahead of time for the board size being played
+    pub(crate) queued_puzzles: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            level_source: LevelSource::FitWindow,
+            assets: AssetPaths::default(),
+            camera: true,
+            win_screen: true,
+            transform: Transform::IDENTITY,
+            persistence: true,
+            shared_puzzle: None,
+            queued_puzzles: 1,
+        }
+    }
+}
+
+pub(crate) fn has_camera(config: Res<Config>) -> bool {
+    config.camera
+}
+
+pub(crate) fn has_win_screen(config: Res<Config>) -> bool {
+    config.win_screen
+}
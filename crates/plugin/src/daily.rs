@@ -0,0 +1,116 @@
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    storage::Storage,
+    toolbar::{ToolbarButton, ToolbarMessage, ToolbarPressed},
+    AppState,
+};
+
+const DAILY_KEY: &str = "daily";
+
+pub(crate) fn plugin(app: &mut App) {
+    let progress = app
+        .world()
+        .resource::<Storage>()
+        .load::<DailyProgress>(DAILY_KEY);
+    app.init_resource::<GameMode>()
+        .insert_resource(progress.unwrap_or_default())
+        .add_systems(OnEnter(AppState::Playing), show_daily_status)
+        .add_systems(OnEnter(AppState::Won), record_daily)
+        .add_systems(OnExit(AppState::Won), reset_game_mode)
+        .add_systems(Update, start_daily.run_if(in_state(AppState::Playing)));
+}
+
+/// Whether the current level is a random one or the daily level of a date
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GameMode {
+    #[default]
+    Random,
+    Daily(NaiveDate),
+}
+
+/// Dates of every daily level completed
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub(crate) struct DailyProgress {
+    completed: BTreeSet<String>,
+}
+
+impl DailyProgress {
+    pub(crate) fn is_completed(&self, date: NaiveDate) -> bool {
+        self.completed.contains(&date_key(date))
+    }
+}
+
+fn date_key(date: NaiveDate) -> String {
+    date.format("%Y-%m-%d").to_string()
+}
+
+pub(crate) fn today() -> NaiveDate {
+    chrono::Utc::now().date_naive()
+}
+
+fn start_daily(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut toolbar_events: EventReader<ToolbarPressed>,
+    mut game_mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let pressed = toolbar_events
+        .read()
+        .filter(|event| ***event == ToolbarButton::Daily)
+        .count()
+        > 0;
+    if !pressed && !keys.just_pressed(KeyCode::KeyT) {
+        return;
+    }
+
+    let today = today();
+    if *game_mode == GameMode::Daily(today) {
+        return;
+    }
+
+    info!("Starting daily level for {today}");
+    *game_mode = GameMode::Daily(today);
+    next_state.set(AppState::Loading);
+}
+
+fn show_daily_status(
+    game_mode: Res<GameMode>,
+    progress: Res<DailyProgress>,
+    mut q_message: Query<&mut Text, With<ToolbarMessage>>,
+) {
+    let GameMode::Daily(date) = *game_mode else {
+        return;
+    };
+
+    let message = if progress.is_completed(date) {
+        format!("Daily {} (completed)", date_key(date))
+    } else {
+        format!("Daily {}", date_key(date))
+    };
+    for mut text in &mut q_message {
+        text.sections[0].value = message.clone();
+    }
+}
+
+fn record_daily(
+    storage: Res<Storage>,
+    game_mode: Res<GameMode>,
+    mut progress: ResMut<DailyProgress>,
+) {
+    let GameMode::Daily(date) = *game_mode else {
+        return;
+    };
+
+    if progress.completed.insert(date_key(date)) {
+        storage.save(DAILY_KEY, &*progress);
+    }
+}
+
+fn reset_game_mode(mut game_mode: ResMut<GameMode>) {
+    *game_mode = GameMode::Random;
+}
@@ -0,0 +1,120 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+
+use dnd_rs_level::{Level, Mark};
+
+use crate::{
+    actions::{self, Change, PlayerAction, UndoRequested},
+    daily::GameMode,
+    hint::HintsUsed,
+    stats::{self, SolveTimer},
+    AppState,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_event::<PuzzleStarted>()
+        .add_event::<CellChanged>()
+        .add_event::<PuzzleSolved>()
+        .add_event::<NewPuzzle>()
+        .add_event::<SetCell>()
+        .add_event::<Undo>()
+        .add_systems(OnEnter(AppState::Playing), send_puzzle_started)
+        .add_systems(
+            OnEnter(AppState::Won),
+            send_puzzle_solved.after(stats::record_solve),
+        )
+        .add_systems(
+            Update,
+            (
+                (handle_set_cell, handle_undo)
+                    .before(actions::apply_actions)
+                    .run_if(in_state(AppState::Playing)),
+                handle_new_puzzle.run_if(not(in_state(AppState::Loading))),
+            ),
+        );
+}
+
+/// Sent when a level is ready to be played, including resumed levels
+#[derive(Event, Clone)]
+pub struct PuzzleStarted {
+    pub level: Level,
+}
+
+/// Sent when a wall or hallway mark is placed or removed on the board, by the player,
+/// a hint or a [`SetCell`] event
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CellChanged {
+    pub x: usize,
+    pub y: usize,
+    pub from: Mark,
+    pub to: Mark,
+}
+
+/// Sent when the level is solved
+#[derive(Event, Clone, Copy, Debug)]
+pub struct PuzzleSolved {
+    pub time: Duration,
+    pub hints: usize,
+}
+
+/// Starts a new random level, abandoning the current one
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct NewPuzzle;
+
+/// Marks a cell on the board like the player would. The change can be undone.
+#[derive(Event, Clone, Copy, Debug)]
+pub struct SetCell {
+    pub x: usize,
+    pub y: usize,
+    pub mark: Mark,
+}
+
+/// Undoes the last change to the board
+#[derive(Event, Clone, Copy, Debug, Default)]
+pub struct Undo;
+
+fn send_puzzle_started(mut events: EventWriter<PuzzleStarted>, q_level: Query<&Level>) {
+    events.send(PuzzleStarted {
+        level: q_level.single().clone(),
+    });
+}
+
+fn send_puzzle_solved(
+    mut events: EventWriter<PuzzleSolved>,
+    timer: Res<SolveTimer>,
+    hints_used: Res<HintsUsed>,
+) {
+    events.send(PuzzleSolved {
+        time: timer.elapsed(),
+        hints: hints_used.0,
+    });
+}
+
+fn handle_new_puzzle(
+    mut events: EventReader<NewPuzzle>,
+    mut game_mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if events.read().count() > 0 {
+        info!("Starting new game");
+        *game_mode = GameMode::Random;
+        next_state.set(AppState::Loading);
+    }
+}
+
+fn handle_set_cell(mut events: EventReader<SetCell>, mut actions: EventWriter<PlayerAction>) {
+    for event in events.read() {
+        actions.send(PlayerAction::single(
+            event.x,
+            event.y,
+            Change::Mark(event.mark),
+        ));
+    }
+}
+
+fn handle_undo(mut events: EventReader<Undo>, mut undo_requests: EventWriter<UndoRequested>) {
+    for _ in events.read() {
+        undo_requests.send(UndoRequested);
+    }
+}
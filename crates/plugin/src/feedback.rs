@@ -0,0 +1,199 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use dnd_rs_level::{Level, ViolationKind};
+
+use crate::{
+    actions, current_board,
+    events::CellChanged,
+    progress,
+    storage::Storage,
+    theme::Theme,
+    toolbar::{ToolbarButton, ToolbarMessage, ToolbarPressed},
+    AppState, Cell, Column, GameComponent, HallwayMark, Row, Wall, CELL_SIZE,
+};
+
+const INDICATORS_KEY: &str = "indicators";
+
+pub(crate) fn plugin(app: &mut App) {
+    let indicators = app
+        .world()
+        .resource::<Storage>()
+        .load::<Indicators>(INDICATORS_KEY);
+    app.insert_resource(indicators.unwrap_or_default())
+        .add_systems(OnExit(AppState::Loading), spawn_violation_indicators)
+        .add_systems(
+            OnEnter(AppState::Playing),
+            update_violation_indicators.after(progress::restore_progress),
+        )
+        .add_systems(
+            Update,
+            (
+                toggle_indicators,
+                update_violation_indicators
+                    .after(actions::apply_actions)
+                    .run_if(
+                        on_event::<CellChanged>()
+                            .or_else(resource_changed::<Indicators>)
+                            .or_else(resource_changed::<Theme>),
+                    ),
+            )
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        );
+}
+
+/// Which live rule violations are highlighted on the board
+#[derive(Resource, Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Indicators {
+    pub(crate) enclosed_monsters: bool,
+    pub(crate) dead_ends: bool,
+    pub(crate) wide_hallways: bool,
+    pub(crate) cut_off_regions: bool,
+}
+
+impl Default for Indicators {
+    fn default() -> Self {
+        Self {
+            enclosed_monsters: true,
+            dead_ends: true,
+            wide_hallways: true,
+            cut_off_regions: true,
+        }
+    }
+}
+
+impl Indicators {
+    fn is_enabled(&self, kind: ViolationKind) -> bool {
+        match kind {
+            ViolationKind::EnclosedMonster => self.enclosed_monsters,
+            ViolationKind::DeadEnd => self.dead_ends,
+            ViolationKind::WideHallway => self.wide_hallways,
+            ViolationKind::CutOff => self.cut_off_regions,
+        }
+    }
+
+    fn any_enabled(&self) -> bool {
+        self.enclosed_monsters || self.dead_ends || self.wide_hallways || self.cut_off_regions
+    }
+}
+
+#[derive(Component)]
+struct ViolationIndicator;
+
+fn spawn_violation_indicators(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    q_cells: Query<(&Transform, &Row, &Column), (With<Cell>, With<Sprite>)>,
+) {
+    for (transform, row, column) in &q_cells {
+        commands.spawn((
+            GameComponent,
+            ViolationIndicator,
+            SpriteBundle {
+                transform: Transform {
+                    translation: transform.translation.with_z(0.5),
+                    scale: CELL_SIZE.extend(0.0),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    color: theme.violation,
+                    ..Default::default()
+                },
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            *row,
+            *column,
+        ));
+    }
+}
+
+// Number keys toggle each indicator, zero or the toolbar button toggles them all
+fn toggle_indicators(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut toolbar_events: EventReader<ToolbarPressed>,
+    storage: Res<Storage>,
+    mut indicators: ResMut<Indicators>,
+    mut q_message: Query<&mut Text, With<ToolbarMessage>>,
+) {
+    let pressed = toolbar_events
+        .read()
+        .filter(|event| ***event == ToolbarButton::Warnings)
+        .count()
+        > 0;
+
+    let message = if keys.just_pressed(KeyCode::Digit1) {
+        indicators.enclosed_monsters = !indicators.enclosed_monsters;
+        state_message("Enclosed monster", indicators.enclosed_monsters)
+    } else if keys.just_pressed(KeyCode::Digit2) {
+        indicators.dead_ends = !indicators.dead_ends;
+        state_message("Dead end", indicators.dead_ends)
+    } else if keys.just_pressed(KeyCode::Digit3) {
+        indicators.wide_hallways = !indicators.wide_hallways;
+        state_message("Wide hallway", indicators.wide_hallways)
+    } else if keys.just_pressed(KeyCode::Digit4) {
+        indicators.cut_off_regions = !indicators.cut_off_regions;
+        state_message("Cut off region", indicators.cut_off_regions)
+    } else if pressed || keys.just_pressed(KeyCode::Digit0) {
+        let enabled = !indicators.any_enabled();
+        *indicators = Indicators {
+            enclosed_monsters: enabled,
+            dead_ends: enabled,
+            wide_hallways: enabled,
+            cut_off_regions: enabled,
+        };
+        state_message("Rule", enabled)
+    } else {
+        return;
+    };
+
+    storage.save(INDICATORS_KEY, &*indicators);
+    for mut text in &mut q_message {
+        text.sections[0].value.clone_from(&message);
+    }
+}
+
+fn state_message(name: &str, enabled: bool) -> String {
+    format!("{name} warnings {}", if enabled { "on" } else { "off" })
+}
+
+fn update_violation_indicators(
+    theme: Res<Theme>,
+    indicators: Res<Indicators>,
+    mut q_indicators: Query<
+        (&Row, &Column, &mut Sprite, &mut Visibility),
+        With<ViolationIndicator>,
+    >,
+    q_walls: Query<(&Row, &Column), With<Wall>>,
+    q_hallway_marks: Query<(&Row, &Column), With<HallwayMark>>,
+    q_level: Query<&Level>,
+) {
+    let level = q_level.single();
+
+    let mut violations = vec![None; level.width() * level.height()];
+    if indicators.any_enabled() {
+        for violation in current_board(level, &q_walls, &q_hallway_marks).violations() {
+            let slot = &mut violations[violation.y() * level.width() + violation.x()];
+            if slot.is_none() && indicators.is_enabled(violation.kind()) {
+                *slot = Some(violation.kind());
+            }
+        }
+    }
+
+    for (row, column, mut sprite, mut visibility) in &mut q_indicators {
+        match violations[row.0 * level.width() + column.0] {
+            Some(kind) => {
+                sprite.color = match kind {
+                    ViolationKind::CutOff => theme.cut_off,
+                    _ => theme.violation,
+                };
+                *visibility = Visibility::Inherited;
+            }
+            None => {
+                *visibility = Visibility::Hidden;
+            }
+        }
+    }
+}
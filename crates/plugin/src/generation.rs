@@ -0,0 +1,343 @@
+use std::{
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Mutex,
+    },
+    task::Poll,
+    time::Duration,
+};
+
+use bevy::prelude::*;
+
+use dnd_rs_level::{Difficulty, Level, LevelBuilder, Puzzle, Solver};
+
+use crate::{
+    config::Config,
+    daily::GameMode,
+    progress::{AbandonedProgress, ResumedProgress},
+    queue::{random_level_builder, PuzzleQueue, QueuedLevel, FALLBACK_LEVEL_SIZE},
+    stats::PuzzleDifficulty,
+    theme::Theme,
+    toolbar::TOOLBAR_TEXT_SIZE,
+    AppState, GameComponent,
+};
+
+const INDICATOR_TEXT_SIZE: f32 = 32.0;
+
+// Show the time taken once generating is slow enough to wonder about it
+const SHOW_ELAPSED_AFTER: Duration = Duration::from_secs(2);
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(
+        Update,
+        (
+            spawn_indicator.run_if(resource_added::<Generation>),
+            update_indicator,
+            finish_generation,
+        )
+            .chain()
+            .run_if(in_state(AppState::Loading).and_then(resource_exists::<Generation>)),
+    );
+}
+
+/// Tasks run on the main thread in the single-threaded web build, so building a level there
+/// still holds up the frames until it is done. Only the threaded web build moves it to a
+/// Web Worker: without shared memory a worker would need its own copy of the module and
+/// to send the level back as a message, which the plugin has no way to set up.
+pub(crate) const BUILDS_ON_MAIN_THREAD: bool =
+    cfg!(all(target_arch = "wasm32", not(target_feature = "atomics")));
+
+/// A level with its difficulty, or why it couldn't be made
+pub(crate) type GeneratedLevel = Result<(Level, Difficulty), &'static str>;
+
+/// A level being built off the main thread, so the game keeps drawing frames while the
+/// generator searches for a level with a unique solution. The level is rated there too.
+pub(crate) struct PendingLevel(Mutex<Receiver<GeneratedLevel>>);
+
+impl PendingLevel {
+    /// Rated by the generator's own search for a unique solution when it has one
+    pub(crate) fn start(builder: LevelBuilder) -> Self {
+        PendingLevel::spawn(move || {
+            let (level, report) = builder.build_with_report()?;
+            let difficulty = report
+                .difficulty
+                .unwrap_or_else(|| Solver::from_level(&level).difficulty());
+            Ok((level, difficulty))
+        })
+    }
+
+    /// The puzzle's solution, solving it first when the puzzle doesn't come with one
+    pub(crate) fn solve(puzzle: Puzzle) -> Self {
+        PendingLevel::rate(move || puzzle.solved_level().ok_or("Puzzle has no solution"))
+    }
+
+    /// A level that is already made, or quick to make, only has to be rated
+    pub(crate) fn rate(
+        level: impl FnOnce() -> Result<Level, &'static str> + Send + 'static,
+    ) -> Self {
+        PendingLevel::spawn(move || {
+            let level = level()?;
+            let difficulty = Solver::from_level(&level).difficulty();
+            Ok((level, difficulty))
+        })
+    }
+
+    fn spawn(generate: impl FnOnce() -> GeneratedLevel + Send + 'static) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        let generate = move || {
+            let _ = sender.send(generate());
+        };
+
+        // The threaded web build runs it on the solver's pool of Web Workers, as tasks run
+        // on the main thread there
+        #[cfg(all(target_arch = "wasm32", target_feature = "atomics"))]
+        rayon::spawn(generate);
+        #[cfg(not(all(target_arch = "wasm32", target_feature = "atomics")))]
+        bevy::tasks::AsyncComputeTaskPool::get()
+            .spawn(async move { generate() })
+            .detach();
+
+        PendingLevel::new(receiver)
+    }
+
+    /// The level the sender's generator builds
+    pub(crate) fn new(receiver: Receiver<GeneratedLevel>) -> Self {
+        PendingLevel(Mutex::new(receiver))
+    }
+
+    /// The level, once it is built
+    pub(crate) fn poll(&self) -> Poll<GeneratedLevel> {
+        match self.0.lock().unwrap().try_recv() {
+            Ok(level) => Poll::Ready(level),
+            Err(TryRecvError::Empty) => Poll::Pending,
+            Err(TryRecvError::Disconnected) => {
+                Poll::Ready(Err("Level generator stopped without a level"))
+            }
+        }
+    }
+}
+
+/// The level for the game being loaded, while it is generated
+#[derive(Resource)]
+pub(crate) struct Generation {
+    /// Only taken when generating is cancelled
+    pending: Option<PendingLevel>,
+    message: String,
+    started: Duration,
+    /// Board size of a random level, which goes to the queue when cancelled
+    random_size: Option<(usize, usize)>,
+    /// Whether the player can go back to the abandoned game or a smaller level instead
+    cancellable: bool,
+}
+
+impl Generation {
+    /// Waits for the level, with a message like "Loading puzzle 2 of 5" on the loading
+    /// indicator
+    pub(crate) fn new(pending: PendingLevel, message: String, now: Duration) -> Self {
+        Generation {
+            pending: Some(pending),
+            message,
+            started: now,
+            random_size: None,
+            cancellable: true,
+        }
+    }
+
+    /// Waits for a random level of the size
+    pub(crate) fn random(pending: PendingLevel, size: (usize, usize), now: Duration) -> Self {
+        Generation {
+            random_size: Some(size),
+            ..Generation::new(
+                pending,
+                format!("Generating {}x{} level", size.0, size.1),
+                now,
+            )
+        }
+    }
+
+    /// Waits for a random level of the fallback size, taking it from the queue when one is
+    /// there. It is small enough to be quick, so it can't be cancelled.
+    fn fallback(queue: &mut PuzzleQueue, now: Duration) -> Self {
+        let pending = match queue.take(FALLBACK_LEVEL_SIZE) {
+            Some(QueuedLevel::Ready(level)) => {
+                let (sender, receiver) = mpsc::channel();
+                let _ = sender.send(Ok(level));
+                PendingLevel::new(receiver)
+            }
+            Some(QueuedLevel::Generating(pending)) => pending,
+            None => PendingLevel::start(random_level_builder(FALLBACK_LEVEL_SIZE)),
+        };
+        Generation {
+            cancellable: false,
+            ..Generation::random(pending, FALLBACK_LEVEL_SIZE, now)
+        }
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct GenerationIndicator;
+
+#[derive(Component)]
+pub(crate) struct IndicatorText;
+
+#[derive(Component)]
+pub(crate) struct BackButton;
+
+fn spawn_indicator(
+    mut commands: Commands,
+    config: Res<Config>,
+    theme: Res<Theme>,
+    generation: Res<Generation>,
+    abandoned: Res<AbandonedProgress>,
+) {
+    // The board's camera comes with the level
+    if config.camera {
+        commands.spawn((GenerationIndicator, Camera2dBundle::default()));
+    }
+
+    commands
+        .spawn((
+            GenerationIndicator,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(24.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                IndicatorText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: INDICATOR_TEXT_SIZE,
+                        color: theme.text,
+                        ..Default::default()
+                    },
+                ),
+            ));
+
+            if generation.cancellable {
+                let label = if abandoned.exists() {
+                    "Back to game (Esc)".to_string()
+                } else {
+                    let (width, height) = FALLBACK_LEVEL_SIZE;
+                    format!("Play a {width}x{height} level (Esc)")
+                };
+                parent
+                    .spawn((
+                        BackButton,
+                        ButtonBundle {
+                            style: Style {
+                                padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                                ..Default::default()
+                            },
+                            background_color: theme.button.into(),
+                            ..Default::default()
+                        },
+                    ))
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(
+                            label,
+                            TextStyle {
+                                font_size: TOOLBAR_TEXT_SIZE,
+                                color: theme.button_text,
+                                ..Default::default()
+                            },
+                        ));
+                    });
+            }
+        });
+}
+
+fn update_indicator(
+    time: Res<Time>,
+    generation: Res<Generation>,
+    mut q_text: Query<&mut Text, With<IndicatorText>>,
+) {
+    let elapsed = time.elapsed().saturating_sub(generation.started);
+    let dots = (elapsed.as_millis() / 400 % 4) as usize;
+    let mut value = format!("{}{:<3}", generation.message, ".".repeat(dots));
+    if elapsed >= SHOW_ELAPSED_AFTER {
+        value += &format!(" {}s", elapsed.as_secs());
+    }
+
+    for mut text in &mut q_text {
+        text.sections[0].value.clone_from(&value);
+    }
+}
+
+/// Spawns the generated level when it is ready. When cancelled, spawns the abandoned game,
+/// or generates a fallback level when there is none.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn finish_generation(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut generation: ResMut<Generation>,
+    keys: Res<ButtonInput<KeyCode>>,
+    q_back: Query<(Entity, &Interaction), With<BackButton>>,
+    mut abandoned: ResMut<AbandonedProgress>,
+    mut resumed: ResMut<ResumedProgress>,
+    mut game_mode: ResMut<GameMode>,
+    mut queue: ResMut<PuzzleQueue>,
+    q_indicator: Query<Entity, With<GenerationIndicator>>,
+) {
+    let cancelled = generation.cancellable
+        && (keys.just_pressed(KeyCode::Escape)
+            || q_back
+                .iter()
+                .any(|(_, interaction)| *interaction == Interaction::Pressed));
+
+    let level = if cancelled {
+        info!("Cancelled generating level");
+        // The generator can't be interrupted, so a random level is kept for a later
+        // game instead
+        if let (Some(size), Some(pending)) = (generation.random_size, generation.pending.take()) {
+            queue.add_generating(size, pending);
+        }
+        abandoned.resume(&mut resumed).map(|(level, mode)| {
+            *game_mode = mode;
+            // The abandoned game's difficulty is still the current one
+            level
+        })
+    } else {
+        match generation
+            .pending
+            .as_ref()
+            .map_or(Poll::Pending, PendingLevel::poll)
+        {
+            Poll::Ready(Ok((level, difficulty))) => {
+                info!("Level difficulty: {difficulty}");
+                commands.insert_resource(PuzzleDifficulty(difficulty));
+                Some(level)
+            }
+            Poll::Ready(Err(err)) => {
+                warn!("Playing a random level instead: {err}");
+                None
+            }
+            Poll::Pending => return,
+        }
+    };
+
+    let Some(level) = level else {
+        *game_mode = GameMode::Random;
+        *generation = Generation::fallback(&mut queue, time.elapsed());
+        for (entity, _) in &q_back {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    };
+
+    commands.spawn((GameComponent, level));
+    commands.remove_resource::<Generation>();
+    for entity in &q_indicator {
+        commands.entity(entity).despawn_recursive();
+    }
+}
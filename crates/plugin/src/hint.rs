@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+
+use dnd_rs_level::{Contradiction, Hint, HintArea, Level, Mark};
+
+use crate::{
+    actions::{Change, PlayerAction},
+    current_board,
+    theme::Theme,
+    toolbar::{ToolbarButton, ToolbarMessage, ToolbarPressed},
+    AppState, Cell, Column, GameComponent, HallwayMark, Row, Wall, CELL_SIZE,
+};
+
+const HINT_HIGHLIGHT_SECONDS: f32 = 2.0;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<HintsUsed>()
+        .init_resource::<ShownHint>()
+        .add_systems(OnEnter(AppState::Loading), reset_hints)
+        .add_systems(
+            Update,
+            (give_hint, fade_hint_highlights).run_if(in_state(AppState::Playing)),
+        );
+}
+
+#[derive(Resource, Default, Deref, DerefMut)]
+pub(crate) struct HintsUsed(pub(crate) usize);
+
+/// The last hint pointed out to the player. Asking again reveals the cell.
+#[derive(Resource, Default)]
+struct ShownHint(Option<Hint>);
+
+#[derive(Component, Deref, DerefMut)]
+struct HintHighlight(Timer);
+
+fn reset_hints(mut hints_used: ResMut<HintsUsed>, mut shown_hint: ResMut<ShownHint>) {
+    hints_used.0 = 0;
+    shown_hint.0 = None;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn give_hint(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut toolbar_events: EventReader<ToolbarPressed>,
+    mut actions: EventWriter<PlayerAction>,
+    mut hints_used: ResMut<HintsUsed>,
+    mut shown_hint: ResMut<ShownHint>,
+    q_walls: Query<(&Row, &Column), With<Wall>>,
+    q_hallway_marks: Query<(&Row, &Column), With<HallwayMark>>,
+    q_cells: Query<(&Transform, &Row, &Column), (With<Cell>, With<Sprite>)>,
+    q_level: Query<&Level>,
+    mut q_message: Query<&mut Text, With<ToolbarMessage>>,
+) {
+    let requested = toolbar_events
+        .read()
+        .filter(|event| ***event == ToolbarButton::Hint)
+        .count()
+        > 0;
+    if !requested && !keys.just_pressed(KeyCode::KeyH) {
+        return;
+    }
+
+    let level = q_level.single();
+    let board = current_board(level, &q_walls, &q_hallway_marks);
+    let hint = board.hint();
+
+    let message = match hint {
+        None => "Nothing left to deduce".to_string(),
+        Some(Hint::Mistake(contradiction)) => {
+            hints_used.0 += 1;
+            match contradiction {
+                Contradiction::Row(row) => {
+                    (0..level.width()).for_each(|x| {
+                        spawn_hint_highlight(&mut commands, &q_cells, &theme, x, row);
+                    });
+                    format!("The walls in row {} don't fit its count", row + 1)
+                }
+                Contradiction::Column(column) => {
+                    (0..level.height()).for_each(|y| {
+                        spawn_hint_highlight(&mut commands, &q_cells, &theme, column, y);
+                    });
+                    format!("The walls in column {} don't fit its count", column + 1)
+                }
+                Contradiction::Rules => {
+                    "No solution follows the rules with these marks".to_string()
+                }
+            }
+        }
+        Some(Hint::Deduction { x, y, mark, .. }) if shown_hint.0 == hint => {
+            hints_used.0 += 1;
+            spawn_hint_highlight(&mut commands, &q_cells, &theme, x, y);
+            actions.send(PlayerAction::single(x, y, Change::Mark(mark)));
+
+            match mark {
+                Mark::Wall => "That cell has to be a wall".to_string(),
+                _ => "That cell has to be hallway".to_string(),
+            }
+        }
+        Some(Hint::Deduction { area, x, y, .. }) => {
+            hints_used.0 += 1;
+
+            let mut highlight = |x, y| spawn_hint_highlight(&mut commands, &q_cells, &theme, x, y);
+
+            match area {
+                HintArea::Row(row) => {
+                    (0..level.width()).for_each(|x| highlight(x, row));
+                    format!("Look at row {}", row + 1)
+                }
+                HintArea::Column(column) => {
+                    (0..level.height()).for_each(|y| highlight(column, y));
+                    format!("Look at column {}", column + 1)
+                }
+                HintArea::Cell => {
+                    highlight(x, y);
+                    "Look at the highlighted cell".to_string()
+                }
+            }
+        }
+    };
+
+    // A revealed hint starts over, anything else can be revealed by asking again
+    shown_hint.0 = if shown_hint.0 == hint { None } else { hint };
+
+    for mut text in &mut q_message {
+        text.sections[0].value = message.clone();
+    }
+}
+
+fn spawn_hint_highlight(
+    commands: &mut Commands,
+    q_cells: &Query<(&Transform, &Row, &Column), (With<Cell>, With<Sprite>)>,
+    theme: &Theme,
+    x: usize,
+    y: usize,
+) {
+    let Some((transform, _, _)) = q_cells.iter().find(|(_, r, c)| r.0 == y && c.0 == x) else {
+        return;
+    };
+
+    commands.spawn((
+        GameComponent,
+        HintHighlight(Timer::from_seconds(HINT_HIGHLIGHT_SECONDS, TimerMode::Once)),
+        SpriteBundle {
+            transform: Transform {
+                translation: transform.translation.with_z(0.6),
+                scale: CELL_SIZE.extend(0.0),
+                ..Default::default()
+            },
+            sprite: Sprite {
+                color: theme.hint_highlight,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    ));
+}
+
+fn fade_hint_highlights(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    time: Res<Time>,
+    mut q_highlights: Query<(Entity, &mut HintHighlight, &mut Sprite)>,
+) {
+    for (entity, mut timer, mut sprite) in &mut q_highlights {
+        timer.tick(time.delta());
+        if timer.finished() {
+            commands.entity(entity).despawn();
+        } else {
+            sprite.color = theme
+                .hint_highlight
+                .with_alpha(theme.hint_highlight.alpha() * timer.fraction_remaining());
+        }
+    }
+}
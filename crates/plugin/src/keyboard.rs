@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+
+use dnd_rs_level::Level;
+
+use crate::{
+    actions::PlayerAction,
+    daily::GameMode,
+    paint::{PaintSettings, PlayerMarks, Tool},
+    stats,
+    theme::Theme,
+    AppState, Cell, Column, GameComponent, Row, CELL_SIZE,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<KeyboardCursor>()
+        .add_systems(OnEnter(AppState::Loading), reset_cursor)
+        .add_systems(OnExit(AppState::Loading), spawn_cursor)
+        .add_systems(
+            Update,
+            (
+                move_cursor,
+                handle_cursor_keys,
+                handle_new_game_key,
+                update_cursor,
+            )
+                .chain()
+                .run_if(in_state(AppState::Playing))
+                .run_if(not(stats::is_stats_page_open)),
+        );
+}
+
+/// Cell selected with the keyboard. Hidden until a movement key is pressed.
+#[derive(Resource, Default)]
+struct KeyboardCursor {
+    x: usize,
+    y: usize,
+    visible: bool,
+}
+
+#[derive(Component)]
+pub(crate) struct CursorHighlight;
+
+fn reset_cursor(mut cursor: ResMut<KeyboardCursor>) {
+    *cursor = KeyboardCursor::default();
+}
+
+fn spawn_cursor(mut commands: Commands, theme: Res<Theme>) {
+    commands.spawn((
+        GameComponent,
+        CursorHighlight,
+        SpriteBundle {
+            transform: Transform::from_scale(CELL_SIZE.extend(0.0)),
+            sprite: Sprite {
+                color: theme.cursor,
+                ..Default::default()
+            },
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+    ));
+}
+
+// Arrow keys or WASD
+fn move_cursor(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    mut cursor: ResMut<KeyboardCursor>,
+    q_level: Query<&Level>,
+) {
+    if mouse_buttons.get_just_pressed().next().is_some() {
+        cursor.visible = false;
+        return;
+    }
+
+    let level = q_level.single();
+
+    let pressed = |codes: [KeyCode; 2]| keys.any_just_pressed(codes);
+    let (x, y) = (cursor.x, cursor.y);
+    let (x, y) = if pressed([KeyCode::ArrowLeft, KeyCode::KeyA]) {
+        (x.saturating_sub(1), y)
+    } else if pressed([KeyCode::ArrowRight, KeyCode::KeyD]) {
+        ((x + 1).min(level.width() - 1), y)
+    } else if pressed([KeyCode::ArrowUp, KeyCode::KeyW]) {
+        (x, y.saturating_sub(1))
+    } else if pressed([KeyCode::ArrowDown, KeyCode::KeyS]) {
+        (x, (y + 1).min(level.height() - 1))
+    } else {
+        return;
+    };
+
+    // The first key press only shows the cursor
+    if cursor.visible {
+        cursor.x = x;
+        cursor.y = y;
+    }
+    cursor.visible = true;
+}
+
+// Space works like a left click, E toggles a hallway mark and Q a question mark
+fn handle_cursor_keys(
+    keys: Res<ButtonInput<KeyCode>>,
+    settings: Res<PaintSettings>,
+    mut cursor: ResMut<KeyboardCursor>,
+    mut actions: EventWriter<PlayerAction>,
+    marks: PlayerMarks,
+) {
+    let tool = if keys.just_pressed(KeyCode::Space) {
+        Tool::Primary
+    } else if keys.just_pressed(KeyCode::KeyE) {
+        Tool::HallwayMark
+    } else if keys.just_pressed(KeyCode::KeyQ) {
+        Tool::QuestionMark
+    } else {
+        return;
+    };
+
+    if cursor.visible {
+        let change = marks.change(tool, settings.controls, (cursor.x, cursor.y));
+        actions.send(PlayerAction::single(cursor.x, cursor.y, change));
+    }
+    cursor.visible = true;
+}
+
+fn handle_new_game_key(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut game_mode: ResMut<GameMode>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    if keys.just_pressed(KeyCode::KeyN) {
+        info!("Starting new game");
+        *game_mode = GameMode::Random;
+        next_state.set(AppState::Loading);
+    }
+}
+
+fn update_cursor(
+    cursor: Res<KeyboardCursor>,
+    q_cells: Query<(&Transform, &Row, &Column), (With<Cell>, With<Sprite>)>,
+    mut q_highlight: Query<
+        (&mut Transform, &mut Visibility),
+        (With<CursorHighlight>, Without<Cell>),
+    >,
+) {
+    let Some((cell, _, _)) = q_cells
+        .iter()
+        .find(|(_, row, column)| row.0 == cursor.y && column.0 == cursor.x)
+    else {
+        return;
+    };
+
+    for (mut transform, mut visibility) in &mut q_highlight {
+        transform.translation = cell.translation.with_z(0.7);
+        *visibility = if cursor.visible {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
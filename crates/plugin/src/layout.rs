@@ -0,0 +1,140 @@
+use bevy::{
+    prelude::*,
+    render::camera::Viewport,
+    window::{PrimaryWindow, WindowResized},
+};
+
+use crate::{config, toolbar::Toolbar, AppState, MainCamera};
+
+/// Cells along the short side of the window when the board size is picked from the window
+const SHORT_SIDE_CELLS: usize = 8;
+const MAX_LONG_SIDE_CELLS: usize = 13;
+const TOOLBAR_COLUMN_WIDTH: f32 = 180.0;
+const TOOLBAR_MARGIN: f32 = 8.0;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_systems(
+        OnEnter(AppState::Playing),
+        update_layout.run_if(config::has_camera),
+    )
+    .add_systems(
+        Update,
+        update_layout
+            .run_if(config::has_camera)
+            .run_if(on_event::<WindowResized>().or_else(toolbar_resized)),
+    );
+}
+
+// The toolbar is spawned with each game and only gets its size after the UI layout,
+// and it wraps to a different size when the orientation changes
+fn toolbar_resized(q_toolbar: Query<(), (With<Toolbar>, Changed<Node>)>) -> bool {
+    !q_toolbar.is_empty()
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Orientation {
+    Portrait,
+    Landscape,
+}
+
+impl Orientation {
+    fn of(size: Vec2) -> Self {
+        if size.y > size.x {
+            Orientation::Portrait
+        } else {
+            Orientation::Landscape
+        }
+    }
+}
+
+/// A board size with roughly the same aspect ratio as the window, headers included
+pub(crate) fn fitting_board_size(window: &Window) -> (usize, usize) {
+    let size = window.size();
+    let aspect = size.max_element() / size.min_element().max(1.0);
+    let long_side = ((SHORT_SIDE_CELLS + 1) as f32 * aspect) as usize;
+    let long_side = long_side
+        .saturating_sub(1)
+        .clamp(SHORT_SIDE_CELLS, MAX_LONG_SIDE_CELLS);
+
+    match Orientation::of(size) {
+        Orientation::Portrait => (SHORT_SIDE_CELLS, long_side),
+        Orientation::Landscape => (long_side, SHORT_SIDE_CELLS),
+    }
+}
+
+// The toolbar is a row along the bottom in portrait and a column on the right in
+// landscape. The board is drawn in the rest of the window.
+fn update_layout(
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    mut q_toolbar: Query<(&mut Style, &Node), With<Toolbar>>,
+    mut q_camera: Query<&mut Camera, With<MainCamera>>,
+) {
+    let Ok(window) = q_windows.get_single() else {
+        return;
+    };
+    let Ok((mut style, node)) = q_toolbar.get_single_mut() else {
+        return;
+    };
+    let Ok(mut camera) = q_camera.get_single_mut() else {
+        return;
+    };
+
+    let orientation = Orientation::of(window.size());
+    let toolbar_style = toolbar_style(orientation);
+    if *style != toolbar_style {
+        info!("Switching to {orientation:?} layout");
+        *style = toolbar_style;
+    }
+
+    // Space taken by the toolbar, in logical pixels
+    let margin = TOOLBAR_MARGIN * 2.0;
+    let reserved = match orientation {
+        Orientation::Portrait => Vec2::new(0.0, node.size().y + margin),
+        Orientation::Landscape => Vec2::new(node.size().x + margin, 0.0),
+    };
+    let physical_size = ((window.size() - reserved).max(Vec2::ONE) * window.scale_factor())
+        .as_uvec2()
+        .min(window.physical_size())
+        .max(UVec2::ONE);
+
+    let unchanged = camera.viewport.as_ref().is_some_and(|viewport| {
+        viewport.physical_position == UVec2::ZERO && viewport.physical_size == physical_size
+    });
+    if !unchanged {
+        camera.viewport = Some(Viewport {
+            physical_position: UVec2::ZERO,
+            physical_size,
+            ..Default::default()
+        });
+    }
+}
+
+fn toolbar_style(orientation: Orientation) -> Style {
+    match orientation {
+        Orientation::Portrait => Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(TOOLBAR_MARGIN),
+            left: Val::Px(TOOLBAR_MARGIN),
+            right: Val::Px(TOOLBAR_MARGIN),
+            flex_direction: FlexDirection::Row,
+            flex_wrap: FlexWrap::Wrap,
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(8.0),
+            row_gap: Val::Px(8.0),
+            ..Default::default()
+        },
+        Orientation::Landscape => Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(TOOLBAR_MARGIN),
+            bottom: Val::Px(TOOLBAR_MARGIN),
+            right: Val::Px(TOOLBAR_MARGIN),
+            width: Val::Px(TOOLBAR_COLUMN_WIDTH),
+            flex_direction: FlexDirection::Column,
+            justify_content: JustifyContent::End,
+            align_items: AlignItems::Stretch,
+            row_gap: Val::Px(8.0),
+            ..Default::default()
+        },
+    }
+}
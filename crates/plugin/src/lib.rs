@@ -3,22 +3,47 @@
 use std::cmp::Ordering;
 
 use bevy::{
-    input::{common_conditions::input_just_pressed, touch::TouchPhase},
+    ecs::{query::QueryFilter, system::SystemParam},
+    input::touch::TouchPhase,
     prelude::*,
     render::camera::ScalingMode,
+    transform::TransformSystem,
     window::PrimaryWindow,
 };
 
-use dnd_rs_level::{CellFloor, CellKind, Level};
+use dnd_rs_level::{Board, CellFloor, CellKind, LevelBuilder};
+
+use config::Config;
+pub use config::{AssetPaths, LevelSource};
+pub use dnd_rs_level::{Level, Mark, Pack, Puzzle};
+pub use events::{CellChanged, NewPuzzle, PuzzleSolved, PuzzleStarted, SetCell, Undo};
+
+mod actions;
+mod autofill;
+mod camera;
+mod check;
+mod config;
+mod daily;
+mod events;
+mod feedback;
+mod generation;
+mod hint;
+mod keyboard;
+mod layout;
+mod pack;
+mod paint;
+mod progress;
+mod queue;
+mod stats;
+mod storage;
+#[cfg(test)]
+mod tests;
+mod theme;
+mod toolbar;
 
 const UNIT_SIZE: f32 = 100.0;
 const OFFSET: f32 = UNIT_SIZE / 2.0;
 
-const HEADER_TEXT_COLOR_TOO_FEW: Color = Color::srgb(0.5, 0.5, 1.0);
-const HEADER_TEXT_COLOR_MATCH: Color = Color::srgb(0.0, 1.0, 0.0);
-const HEADER_TEXT_COLOR_TOO_MANY: Color = Color::srgb(1.0, 0.0, 0.0);
-const QUESTION_MARK_COLOR: Color = Color::srgb(1.0, 0.5, 0.0);
-
 const TEXT_SIZE: f32 = UNIT_SIZE * 0.75;
 
 const PADDING_TOP: f32 = 0.0;
@@ -26,12 +51,9 @@ const PADDING_LEFT: f32 = 0.0;
 const PADDING_BOTTOM: f32 = UNIT_SIZE * 0.3;
 const PADDING_RIGHT: f32 = UNIT_SIZE * 0.3;
 
-const WALL_COLOR: Color = Color::srgb(0.0, 0.0, 1.0);
-const CELL_COLOR: Color = Color::srgb(0.5, 0.5, 0.5);
-const BORDER_COLOR: Color = Color::srgb(0.0, 0.0, 0.0);
-
 const BORDER_WIDTH: f32 = UNIT_SIZE * 0.05;
 const CELL_SIZE: Vec2 = Vec2::new(UNIT_SIZE - BORDER_WIDTH, UNIT_SIZE - BORDER_WIDTH);
+const HALLWAY_MARK_SIZE: Vec2 = Vec2::new(UNIT_SIZE * 0.2, UNIT_SIZE * 0.2);
 
 // On web, hide image assets until they are all loaded.
 // We can't do this always as it breaks on Android. Besides, non-web platforms load assets quickly.
@@ -42,7 +64,6 @@ const HIDE_IMAGES_UNTIL_ALL_LOADED: bool = cfg!(all(
 
 // TODO:
 // - Handle long press events like right click
-// - Add indicator when monster is in a blind alley
 // - Add interface settings to change level size
 
 pub struct DungeonsAndDiagramsPlugin {
@@ -50,48 +71,143 @@ pub struct DungeonsAndDiagramsPlugin {
 }
 
 impl Default for DungeonsAndDiagramsPlugin {
+    /// Picks the board size from the window dimensions
     fn default() -> Self {
-        Self::new(8, 8)
+        Self {
+            config: Config::default(),
+        }
     }
 }
 
 impl DungeonsAndDiagramsPlugin {
     pub fn new(width: usize, height: usize) -> Self {
-        Self {
-            config: Config { width, height },
-        }
+        Self::default().with_level_source(LevelSource::Random { width, height })
+    }
+
+    /// Play the same level every game
+    pub fn with_level(self, level: Level) -> Self {
+        self.with_level_source(LevelSource::Fixed(level))
+    }
+
+    /// Play the puzzles of a pack in order, continuing where the player left off
+    pub fn with_pack(self, pack: Pack) -> Self {
+        assert!(!pack.puzzles.is_empty(), "Pack has no puzzles");
+        self.with_level_source(LevelSource::Pack(pack))
+    }
+
+    /// Start with this puzzle, for example one read with [`Puzzle::from_share_code`], and
+    /// continue with the level source after it
+    pub fn with_shared_puzzle(mut self, puzzle: Puzzle) -> Self {
+        self.config.shared_puzzle = Some(puzzle);
+        self
+    }
+
+    /// Random levels to keep generated ahead of time, so the next game starts right away
+    /// even on large boards. One by default, and zero generates each level when it is needed.
+    /// The single-threaded web build never generates ahead of time.
+    pub fn with_queued_puzzles(mut self, count: usize) -> Self {
+        self.config.queued_puzzles = count;
+        self
+    }
+
+    pub fn with_level_source(mut self, level_source: LevelSource) -> Self {
+        self.config.level_source = level_source;
+        self
+    }
+
+    pub fn with_asset_paths(mut self, assets: AssetPaths) -> Self {
+        self.config.assets = assets;
+        self
+    }
+
+    /// Don't spawn a camera, for apps that show the board with their own. Add
+    /// [`MainCamera`] to that camera so clicks and touches reach the board.
+    pub fn without_camera(mut self) -> Self {
+        self.config.camera = false;
+        self
+    }
+
+    /// Don't show confetti and the summary when the level is solved. A key, click or touch
+    /// still starts the next game, as does [`NewPuzzle`].
+    pub fn without_win_screen(mut self) -> Self {
+        self.config.win_screen = false;
+        self
+    }
+
+    /// Don't load or save progress, statistics and settings, so every run starts fresh
+    pub fn without_persistence(mut self) -> Self {
+        self.config.persistence = false;
+        self
+    }
+
+    /// Position and scale of the board in the world. Cells are 100 units apart
+    /// before scaling, and the bottom left corner of the board is at the origin.
+    pub fn with_transform(mut self, transform: Transform) -> Self {
+        self.config.transform = transform;
+        self
     }
 }
 
 impl Plugin for DungeonsAndDiagramsPlugin {
     fn build(&self, app: &mut App) {
         app.init_state::<AppState>()
-            .insert_resource(self.config)
+            .insert_resource(self.config.clone())
+            .insert_resource(storage::Storage {
+                persistent: self.config.persistence,
+            })
             .insert_resource(RandomSource(fastrand::Rng::new()))
             .insert_resource(AssetsLoading(Vec::new()))
+            .add_plugins((
+                (
+                    actions::plugin,
+                    autofill::plugin,
+                    camera::plugin,
+                    check::plugin,
+                    daily::plugin,
+                    events::plugin,
+                    feedback::plugin,
+                    generation::plugin,
+                    hint::plugin,
+                ),
+                (
+                    keyboard::plugin,
+                    layout::plugin,
+                    pack::plugin,
+                    paint::plugin,
+                    progress::plugin,
+                    queue::plugin,
+                    stats::plugin,
+                    theme::plugin,
+                    toolbar::plugin,
+                ),
+            ))
             .add_systems(
                 OnEnter(AppState::Loading),
-                (
-                    spawn_static_components,
-                    generate_level,
-                    spawn_level_components,
-                )
-                    .chain(),
+                (despawn_game, generate_level).chain(),
             )
             .add_systems(OnExit(AppState::Loading), handle_game_ready)
-            .add_systems(OnEnter(AppState::Won), spawn_confetti)
-            .add_systems(OnExit(AppState::Won), despawn_game)
+            .add_systems(
+                OnEnter(AppState::Won),
+                (
+                    start_watch_confetti_timer,
+                    (spawn_confetti, spawn_win_summary.after(stats::record_solve))
+                        .run_if(config::has_win_screen),
+                ),
+            )
             .add_systems(
                 Update,
                 (
+                    (spawn_static_components, spawn_level_components)
+                        .chain()
+                        .after(generation::finish_generation)
+                        .run_if(in_state(AppState::Loading).and_then(level_added)),
                     check_loading_completed.run_if(in_state(AppState::Loading)),
                     (
-                        handle_left_click.run_if(input_just_pressed(MouseButton::Left)),
-                        handle_right_click.run_if(input_just_pressed(MouseButton::Right)),
-                        handle_touch,
                         update_row_header_colors,
                         update_column_header_colors,
-                        check_level_completed,
+                        check_level_completed
+                            .after(actions::apply_actions)
+                            .run_if(on_event::<CellChanged>()),
                     )
                         .run_if(in_state(AppState::Playing)),
                     (
@@ -101,6 +217,10 @@ impl Plugin for DungeonsAndDiagramsPlugin {
                     )
                         .run_if(in_state(AppState::Won)),
                 ),
+            )
+            .add_systems(
+                PostUpdate,
+                adopt_board_entities.before(TransformSystem::TransformPropagate),
             );
     }
 }
@@ -116,17 +236,16 @@ enum AppState {
 #[derive(Resource, Deref, DerefMut)]
 struct AssetsLoading(Vec<UntypedHandle>);
 
-#[derive(Resource, Clone, Copy)]
-struct Config {
-    width: usize,
-    height: usize,
-}
-
 #[derive(Resource, Deref, DerefMut)]
 struct RandomSource(fastrand::Rng);
 
+/// The camera showing the board, used to find the cells under the cursor
+#[derive(Component)]
+pub struct MainCamera;
+
+/// Parent of everything drawn on the board, placed by the configured transform
 #[derive(Component)]
-struct MainCamera;
+struct BoardRoot;
 
 #[derive(Component)]
 struct GameComponent;
@@ -137,6 +256,13 @@ struct Cell;
 #[derive(Component, Deref)]
 struct HeaderText(usize);
 
+/// How the walls in a header's line compare to its count
+#[derive(Component, Clone, Copy)]
+struct HeaderStatus(Ordering);
+
+#[derive(Component)]
+struct Border;
+
 #[derive(Component, Clone, Copy, Deref)]
 struct Row(usize);
 
@@ -158,6 +284,9 @@ struct Monster;
 #[derive(Component, Default)]
 struct QuestionMark;
 
+#[derive(Component, Default)]
+struct HallwayMark;
+
 #[derive(Bundle, Default)]
 struct FloorBundle {
     cell: Cell,
@@ -176,23 +305,45 @@ struct TreasureBundle {
     treasure: Treasure,
 }
 
-fn spawn_static_components(mut commands: Commands, config: Res<Config>) {
+fn spawn_static_components(
+    mut commands: Commands,
+    config: Res<Config>,
+    theme: Res<theme::Theme>,
+    q_level: Query<&Level>,
+) {
     info!("Spawning static components");
 
-    let width = (config.width as f32 + 1.0) * UNIT_SIZE + PADDING_LEFT + PADDING_RIGHT;
-    let height = (config.height as f32 + 1.0) * UNIT_SIZE + PADDING_TOP + PADDING_BOTTOM;
+    let level = q_level.single();
 
-    let mut camera_2d = Camera2dBundle::default();
-    camera_2d.projection.scaling_mode = ScalingMode::AutoMin {
-        min_width: width,
-        min_height: height,
-    };
-    camera_2d.transform = Transform::from_xyz(width / 2.0, height / 2.0, 0.0);
-    commands.spawn((GameComponent, camera_2d, MainCamera));
+    let width = (level.width() as f32 + 1.0) * UNIT_SIZE + PADDING_LEFT + PADDING_RIGHT;
+    let height = (level.height() as f32 + 1.0) * UNIT_SIZE + PADDING_TOP + PADDING_BOTTOM;
+
+    let root = commands
+        .spawn((
+            GameComponent,
+            BoardRoot,
+            SpatialBundle::from_transform(config.transform),
+        ))
+        .id();
+
+    // The camera moves in board coordinates under the board root, so it stays
+    // centred on the board wherever the configured transform places it
+    if config.camera {
+        let mut camera_2d = Camera2dBundle::default();
+        camera_2d.projection.scaling_mode = ScalingMode::AutoMin {
+            min_width: width,
+            min_height: height,
+        };
+        camera_2d.transform = Transform::from_xyz(width / 2.0, height / 2.0, 0.0);
+        commands
+            .spawn((GameComponent, camera_2d, MainCamera))
+            .set_parent(root);
+    }
 
     // Paint border as background
     commands.spawn((
         GameComponent,
+        Border,
         SpriteBundle {
             transform: Transform {
                 translation: Vec3::new(
@@ -201,14 +352,14 @@ fn spawn_static_components(mut commands: Commands, config: Res<Config>) {
                     -1.0,
                 ),
                 scale: Vec3::new(
-                    config.width as f32 * UNIT_SIZE + BORDER_WIDTH,
-                    config.height as f32 * UNIT_SIZE + BORDER_WIDTH,
+                    level.width() as f32 * UNIT_SIZE + BORDER_WIDTH,
+                    level.height() as f32 * UNIT_SIZE + BORDER_WIDTH,
                     0.0,
                 ),
                 ..Default::default()
             },
             sprite: Sprite {
-                color: BORDER_COLOR,
+                color: theme.border,
                 ..Default::default()
             },
             ..Default::default()
@@ -216,8 +367,8 @@ fn spawn_static_components(mut commands: Commands, config: Res<Config>) {
     ));
 
     // Spawn cells
-    for x in 0..config.width {
-        for y in 0..config.height {
+    for x in 0..level.width() {
+        for y in 0..level.height() {
             let pos = (Row(y), Column(x));
             commands.spawn((
                 GameComponent,
@@ -233,7 +384,7 @@ fn spawn_static_components(mut commands: Commands, config: Res<Config>) {
                         ..Default::default()
                     },
                     sprite: Sprite {
-                        color: CELL_COLOR,
+                        color: theme.cell,
                         ..Default::default()
                     },
                     ..Default::default()
@@ -265,6 +416,7 @@ fn check_loading_completed(
 
 fn handle_game_ready(
     mut commands: Commands,
+    theme: Res<theme::Theme>,
     q_level: Query<&Level>,
     mut q_images: Query<&mut Transform, With<Handle<Image>>>,
 ) {
@@ -287,42 +439,48 @@ fn handle_game_ready(
 
     let text_style = TextStyle {
         font_size: TEXT_SIZE,
-        color: HEADER_TEXT_COLOR_TOO_FEW,
+        color: theme.header_too_few,
         ..Default::default()
     };
 
     column_headers.iter().enumerate().for_each(|(i, value)| {
-        commands.spawn((
-            GameComponent,
-            Text2dBundle {
-                text: Text::from_section(value.to_string(), text_style.clone()),
-                transform: Transform::from_xyz(
-                    (i as f32 + 1.0) * UNIT_SIZE + OFFSET + PADDING_LEFT,
-                    height - (OFFSET + PADDING_TOP),
-                    100.0,
-                ),
-                ..Default::default()
-            },
-            Column(i),
-            HeaderText(*value),
-        ));
+        commands
+            .spawn((
+                GameComponent,
+                Text2dBundle {
+                    text: Text::from_section(value.to_string(), text_style.clone()),
+                    transform: Transform::from_xyz(
+                        (i as f32 + 1.0) * UNIT_SIZE + OFFSET + PADDING_LEFT,
+                        height - (OFFSET + PADDING_TOP),
+                        100.0,
+                    ),
+                    ..Default::default()
+                },
+                Column(i),
+                HeaderText(*value),
+                HeaderStatus(Ordering::Less),
+            ))
+            .with_children(theme::spawn_header_cue);
     });
 
     row_headers.iter().enumerate().for_each(|(i, value)| {
-        commands.spawn((
-            GameComponent,
-            Text2dBundle {
-                text: Text::from_section(value.to_string(), text_style.clone()),
-                transform: Transform::from_xyz(
-                    OFFSET + PADDING_LEFT,
-                    height - ((i as f32 + 1.0) * UNIT_SIZE + OFFSET + PADDING_TOP),
-                    100.0,
-                ),
-                ..Default::default()
-            },
-            Row(i),
-            HeaderText(*value),
-        ));
+        commands
+            .spawn((
+                GameComponent,
+                Text2dBundle {
+                    text: Text::from_section(value.to_string(), text_style.clone()),
+                    transform: Transform::from_xyz(
+                        OFFSET + PADDING_LEFT,
+                        height - ((i as f32 + 1.0) * UNIT_SIZE + OFFSET + PADDING_TOP),
+                        100.0,
+                    ),
+                    ..Default::default()
+                },
+                Row(i),
+                HeaderText(*value),
+                HeaderStatus(Ordering::Less),
+            ))
+            .with_children(theme::spawn_header_cue);
     });
 
     if HIDE_IMAGES_UNTIL_ALL_LOADED {
@@ -333,23 +491,106 @@ fn handle_game_ready(
     }
 }
 
-fn generate_level(mut commands: Commands, config: Res<Config>) {
-    info!("Generating level");
+#[allow(clippy::too_many_arguments)]
+fn generate_level(
+    mut commands: Commands,
+    config: Res<Config>,
+    time: Res<Time>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    mut game_mode: ResMut<daily::GameMode>,
+    mut resumed: ResMut<progress::ResumedProgress>,
+    mut pack_position: ResMut<pack::PackPosition>,
+    mut shared_puzzle: ResMut<pack::SharedPuzzle>,
+    mut puzzle_queue: ResMut<queue::PuzzleQueue>,
+    storage: Res<storage::Storage>,
+) {
+    if let Some(puzzle) = shared_puzzle.take() {
+        info!("Playing shared puzzle");
+        *game_mode = daily::GameMode::Random;
+        commands.insert_resource(generation::Generation::new(
+            generation::PendingLevel::solve(puzzle),
+            "Loading shared puzzle".to_string(),
+            time.elapsed(),
+        ));
+        return;
+    }
 
-    // TODO: Trigger win condition in any solution when uniqueness not guaranteed
+    if let Some((level, mode)) = resumed.level_for(&config.level_source) {
+        info!("Resuming level");
+        *game_mode = mode;
+        commands.insert_resource(generation::Generation::new(
+            generation::PendingLevel::rate(move || Ok(level)),
+            "Loading saved game".to_string(),
+            time.elapsed(),
+        ));
+        return;
+    }
 
-    commands.spawn((
-        GameComponent,
-        Level::builder(config.width, config.height)
-            .check_too_many_walls()
-            .check_unique_solution()
-            .build()
-            .unwrap(),
+    info!("Generating level");
+
+    let (pending, message) = match (&config.level_source, *game_mode) {
+        (_, daily::GameMode::Daily(date)) => (
+            generation::PendingLevel::start(LevelBuilder::daily(date)),
+            "Generating daily level".to_string(),
+        ),
+        (LevelSource::Fixed(level), _) => {
+            let level = level.clone();
+            (
+                generation::PendingLevel::rate(move || Ok(level)),
+                "Loading level".to_string(),
+            )
+        }
+        (LevelSource::Custom(source), _) => {
+            let source = source.clone();
+            (
+                generation::PendingLevel::rate(move || Ok(source())),
+                "Loading level".to_string(),
+            )
+        }
+        (LevelSource::Pack(pack), _) => {
+            let (number, puzzle) = pack_position.next_puzzle(pack, &storage);
+            (
+                generation::PendingLevel::solve(puzzle),
+                format!("Loading puzzle {number} of {}", pack.puzzles.len()),
+            )
+        }
+        (LevelSource::Random { .. } | LevelSource::FitWindow, _) => {
+            let size = queue::random_level_size(&config, q_windows.get_single().ok())
+                .expect("Level source gives random levels");
+            let pending = match puzzle_queue.take(size) {
+                Some(queue::QueuedLevel::Ready((level, difficulty))) => {
+                    info!("Playing level generated ahead of time");
+                    commands.insert_resource(stats::PuzzleDifficulty(difficulty));
+                    commands.spawn((GameComponent, level));
+                    return;
+                }
+                Some(queue::QueuedLevel::Generating(pending)) => pending,
+                None => generation::PendingLevel::start(queue::random_level_builder(size)),
+            };
+            commands.insert_resource(generation::Generation::random(
+                pending,
+                size,
+                time.elapsed(),
+            ));
+            return;
+        }
+    };
+    commands.insert_resource(generation::Generation::new(
+        pending,
+        message,
+        time.elapsed(),
     ));
 }
 
+/// Run condition for spawning the board once the level is there, which can take a
+/// while when it is generated
+fn level_added(q_level: Query<(), Added<Level>>) -> bool {
+    !q_level.is_empty()
+}
+
 fn spawn_level_components(
     mut commands: Commands,
+    config: Res<Config>,
     mut rnd: ResMut<RandomSource>,
     asset_server: Res<AssetServer>,
     q_level: Query<&Level>,
@@ -395,7 +636,7 @@ fn spawn_level_components(
                     position,
                 ));
 
-                let texture = asset_server.load("treasure.png");
+                let texture = asset_server.load(&config.assets.treasure);
                 assets_loading.push(texture.clone().untyped());
 
                 commands.spawn((
@@ -428,7 +669,10 @@ fn spawn_level_components(
                     position,
                 ));
 
-                let texture = asset_server.load(format!("monsters/{}.png", rnd.u32(1..=40)));
+                let path = rnd
+                    .choice(&config.assets.monsters)
+                    .expect("no monster images configured");
+                let texture = asset_server.load(path);
                 assets_loading.push(texture.clone().untyped());
 
                 commands.spawn((
@@ -458,130 +702,109 @@ fn spawn_level_components(
     commands.insert_resource(AssetsLoading(assets_loading));
 }
 
-fn handle_left_click(
-    mut commands: Commands,
-    q_walls: Query<(Entity, &Transform), With<Wall>>,
-    q_empty_cells: Query<(&Transform, &Row, &Column), (With<Floor>, Without<Wall>)>,
-    q_windows: Query<&Window, With<PrimaryWindow>>,
-    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-) {
-    let Some(cursor_position) = get_cursor_position_in_world(q_windows, q_camera) else {
-        // Cursor is not in camera view
-        return;
-    };
-
-    execute_primary_action(&mut commands, &q_walls, &q_empty_cells, cursor_position);
+fn find_cell_at<F: QueryFilter>(
+    q_cells: &Query<(&Transform, &Row, &Column), F>,
+    pos: Vec2,
+) -> Option<(usize, usize)> {
+    q_cells
+        .iter()
+        .find(|(transform, _, _)| is_cursor_in_cell(pos, transform))
+        .map(|(_, row, column)| (column.0, row.0))
 }
 
-fn handle_touch(
-    mut touch_events: EventReader<TouchInput>,
-    mut commands: Commands,
-    q_walls: Query<(Entity, &Transform), With<Wall>>,
-    q_empty_cells: Query<(&Transform, &Row, &Column), (With<Floor>, Without<Wall>)>,
-    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-) {
-    for event in touch_events.read() {
-        if event.phase == TouchPhase::Ended {
-            let Some(position) = viewport_to_world_position(&q_camera, event.position) else {
-                continue;
-            };
-
-            execute_primary_action(&mut commands, &q_walls, &q_empty_cells, position);
-        }
-    }
+fn spawn_wall(
+    commands: &mut Commands,
+    theme: &theme::Theme,
+    translation: Vec3,
+    row: Row,
+    column: Column,
+) -> Entity {
+    commands
+        .spawn((
+            GameComponent,
+            Wall,
+            SpriteBundle {
+                transform: Transform {
+                    translation: translation.with_z(1.0),
+                    scale: CELL_SIZE.extend(0.0),
+                    ..Default::default()
+                },
+                sprite: Sprite {
+                    color: theme.wall,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            row,
+            column,
+        ))
+        .id()
 }
 
-fn execute_primary_action(
+fn spawn_hallway_mark(
     commands: &mut Commands,
-    q_walls: &Query<(Entity, &Transform), With<Wall>>,
-    q_empty_cells: &Query<(&Transform, &Row, &Column), (With<Floor>, Without<Wall>)>,
-    pos: Vec2,
-) {
-    // If a wall is clicked, remove it
-    for (entity, transform) in q_walls {
-        if is_cursor_in_cell(pos, transform) {
-            commands.entity(entity).despawn();
-            return;
-        }
-    }
-
-    // If an empty cell is clicked, add a wall
-    for (transform, row, column) in q_empty_cells {
-        if is_cursor_in_cell(pos, transform) {
-            commands.spawn((
-                GameComponent,
-                Wall,
-                SpriteBundle {
-                    transform: Transform {
-                        translation: transform.translation.with_z(1.0),
-                        scale: CELL_SIZE.extend(0.0),
-                        ..Default::default()
-                    },
-                    sprite: Sprite {
-                        color: WALL_COLOR,
-                        ..Default::default()
-                    },
+    theme: &theme::Theme,
+    translation: Vec3,
+    row: Row,
+    column: Column,
+) -> Entity {
+    commands
+        .spawn((
+            GameComponent,
+            HallwayMark,
+            SpriteBundle {
+                transform: Transform {
+                    translation: translation.with_z(1.0),
+                    scale: HALLWAY_MARK_SIZE.extend(0.0),
                     ..Default::default()
                 },
-                *row,
-                *column,
-            ));
-            return;
-        }
-    }
+                sprite: Sprite {
+                    color: theme.hallway_mark,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            row,
+            column,
+        ))
+        .id()
 }
 
-fn handle_right_click(
-    mut commands: Commands,
-    q_question_marks: Query<(Entity, &Transform), With<QuestionMark>>,
-    q_cells: Query<(&Transform, &Row, &Column), (With<Cell>, Without<QuestionMark>)>,
-    q_windows: Query<&Window, With<PrimaryWindow>>,
-    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-) {
-    let Some(cursor_position) = get_cursor_position_in_world(q_windows, q_camera) else {
-        // Cursor is not in camera view
-        return;
-    };
-
-    // If a question mark is clicked, remove it
-    for (entity, transform) in &q_question_marks {
-        if is_cursor_in_cell(cursor_position, transform) {
-            commands.entity(entity).despawn();
-            return;
-        }
-    }
-
-    // If a cell is clicked, add a question mark
-    for (transform, row, column) in &q_cells {
-        if is_cursor_in_cell(cursor_position, transform) {
-            commands.spawn((
-                GameComponent,
-                QuestionMark,
-                Text2dBundle {
-                    text: Text::from_section(
-                        "?",
-                        TextStyle {
-                            font_size: TEXT_SIZE,
-                            color: QUESTION_MARK_COLOR,
-                            ..Default::default()
-                        },
-                    ),
-                    transform: Transform {
-                        translation: transform.translation.with_z(2.0),
+fn spawn_question_mark(
+    commands: &mut Commands,
+    theme: &theme::Theme,
+    translation: Vec3,
+    row: Row,
+    column: Column,
+) -> Entity {
+    commands
+        .spawn((
+            GameComponent,
+            QuestionMark,
+            Text2dBundle {
+                text: Text::from_section(
+                    "?",
+                    TextStyle {
+                        font_size: TEXT_SIZE,
+                        color: theme.question_mark,
                         ..Default::default()
                     },
+                ),
+                transform: Transform {
+                    translation: translation.with_z(2.0),
                     ..Default::default()
                 },
-                *row,
-                *column,
-            ));
-            return;
-        }
-    }
+                ..Default::default()
+            },
+            row,
+            column,
+        ))
+        .id()
 }
 
 fn update_row_header_colors(
-    mut q_row_headers: Query<(&Row, &mut Text, &HeaderText)>,
+    mut q_row_headers: Query<(&Row, &mut Text, &HeaderText, &mut HeaderStatus)>,
+    theme: Res<theme::Theme>,
     q_walls: Query<&Row, With<Wall>>,
     q_level: Query<&Level>,
 ) {
@@ -596,18 +819,16 @@ fn update_row_header_colors(
     // Update row headers colors
     q_row_headers
         .iter_mut()
-        .for_each(|(row, mut text, header_text)| {
+        .for_each(|(row, mut text, header_text, mut status)| {
             let row_wall_count = row_walls[row.0];
-            text.sections[0].style.color = match row_wall_count.cmp(&header_text.0) {
-                Ordering::Less => HEADER_TEXT_COLOR_TOO_FEW,
-                Ordering::Equal => HEADER_TEXT_COLOR_MATCH,
-                Ordering::Greater => HEADER_TEXT_COLOR_TOO_MANY,
-            };
+            status.0 = row_wall_count.cmp(&header_text.0);
+            text.sections[0].style.color = theme.header_color(status.0);
         });
 }
 
 fn update_column_header_colors(
-    mut q_column_headers: Query<(&Column, &mut Text, &HeaderText)>,
+    mut q_column_headers: Query<(&Column, &mut Text, &HeaderText, &mut HeaderStatus)>,
+    theme: Res<theme::Theme>,
     q_walls: Query<&Column, With<Wall>>,
     q_level: Query<&Level>,
 ) {
@@ -622,16 +843,29 @@ fn update_column_header_colors(
     // Update column headers colors
     q_column_headers
         .iter_mut()
-        .for_each(|(column, mut text, header_text)| {
+        .for_each(|(column, mut text, header_text, mut status)| {
             let column_wall_count = column_walls[column.0];
-            text.sections[0].style.color = match column_wall_count.cmp(&header_text.0) {
-                Ordering::Less => HEADER_TEXT_COLOR_TOO_FEW,
-                Ordering::Equal => HEADER_TEXT_COLOR_MATCH,
-                Ordering::Greater => HEADER_TEXT_COLOR_TOO_MANY,
-            };
+            status.0 = column_wall_count.cmp(&header_text.0);
+            text.sections[0].style.color = theme.header_color(status.0);
         });
 }
 
+// Collect the player's marks into a board for checking against the rules
+fn current_board<'a>(
+    level: &Level,
+    walls: impl IntoIterator<Item = (&'a Row, &'a Column)>,
+    hallway_marks: impl IntoIterator<Item = (&'a Row, &'a Column)>,
+) -> Board {
+    let mut board = Board::new(level);
+    for (row, column) in hallway_marks {
+        board.set_mark(column.0, row.0, Mark::Hallway);
+    }
+    for (row, column) in walls {
+        board.set_mark(column.0, row.0, Mark::Wall);
+    }
+    board
+}
+
 fn is_cursor_in_cell(cursor: Vec2, cell: &Transform) -> bool {
     let center = cell.translation.xy();
     let half_size = CELL_SIZE / 2.0;
@@ -645,19 +879,53 @@ fn is_cursor_in_cell(cursor: Vec2, cell: &Transform) -> bool {
         && cursor.y <= top_right.y
 }
 
-fn get_cursor_position_in_world(
-    q_windows: Query<&Window, With<PrimaryWindow>>,
-    q_camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-) -> Option<Vec2> {
-    viewport_to_world_position(&q_camera, q_windows.single().cursor_position()?)
+/// Finds where the cursor and touches are on the board
+#[derive(SystemParam)]
+struct BoardPointer<'w, 's> {
+    q_windows: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    q_camera: Query<'w, 's, (&'static Camera, &'static GlobalTransform), With<MainCamera>>,
+    q_root: Query<'w, 's, &'static GlobalTransform, With<BoardRoot>>,
+}
+
+impl BoardPointer<'_, '_> {
+    fn cursor_position(&self) -> Option<Vec2> {
+        self.viewport_to_board(self.q_windows.get_single().ok()?.cursor_position()?)
+    }
+
+    fn viewport_to_board(&self, position: Vec2) -> Option<Vec2> {
+        let (camera, camera_transform) = self.q_camera.get_single().ok()?;
+        let world = camera.viewport_to_world_2d(camera_transform, position)?;
+        let root = self.q_root.get_single().ok()?;
+        Some(
+            root.affine()
+                .inverse()
+                .transform_point3(world.extend(0.0))
+                .xy(),
+        )
+    }
 }
 
-fn viewport_to_world_position(
-    q_camera: &Query<(&Camera, &GlobalTransform), With<MainCamera>>,
-    position: Vec2,
-) -> Option<Vec2> {
-    let (camera, camera_transform) = q_camera.single();
-    camera.viewport_to_world_2d(camera_transform, position)
+// Board entities are spawned in board coordinates and moved under the board root
+fn adopt_board_entities(
+    mut commands: Commands,
+    q_root: Query<Entity, With<BoardRoot>>,
+    q_entities: Query<
+        Entity,
+        (
+            Added<GameComponent>,
+            With<Transform>,
+            Without<Parent>,
+            Without<Node>,
+            Without<BoardRoot>,
+        ),
+    >,
+) {
+    let Ok(root) = q_root.get_single() else {
+        return;
+    };
+    for entity in &q_entities {
+        commands.entity(root).add_child(entity);
+    }
 }
 
 #[derive(Component)]
@@ -678,8 +946,9 @@ fn check_level_completed(
 ) {
     let level = q_level.single();
 
+    // Any solution wins, as puzzles from packs and share codes may have more than one
     let is_completed = q_walls.iter().count() == level.iter().filter(|c| c.has_wall()).count()
-        && q_walls.iter().all(|(r, c)| level.is_wall(c.0, r.0));
+        && current_board(level, &q_walls, []).is_solved();
     if is_completed {
         info!("Level completed!");
         next_state.set(AppState::Won);
@@ -718,12 +987,82 @@ fn spawn_confetti(mut commands: Commands, mut rnd: ResMut<RandomSource>, q_level
         ));
     }
 
+    // TODO: Spawn some text and button to restart
+}
+
+// Also without the win screen, so the click placing the last wall doesn't start a new game
+fn start_watch_confetti_timer(mut commands: Commands) {
     commands.spawn((
         GameComponent,
         WatchConfettiTimer(Timer::from_seconds(1.0, TimerMode::Once)),
     ));
+}
 
-    // TODO: Spawn some text and button to restart
+#[allow(clippy::too_many_arguments)]
+fn spawn_win_summary(
+    mut commands: Commands,
+    theme: Res<theme::Theme>,
+    hints_used: Res<hint::HintsUsed>,
+    mistakes: Res<check::Mistakes>,
+    solve_timer: Res<stats::SolveTimer>,
+    difficulty: Res<stats::PuzzleDifficulty>,
+    statistics: Res<stats::Statistics>,
+    game_mode: Res<daily::GameMode>,
+    q_level: Query<&Level>,
+) {
+    let level = q_level.single();
+
+    let mut lines = Vec::new();
+    if let daily::GameMode::Daily(date) = *game_mode {
+        lines.push(format!(
+            "Daily level of {} completed",
+            date.format("%Y-%m-%d")
+        ));
+    }
+    lines.extend([
+        format!("Time: {}", stats::format_time(solve_timer.elapsed_secs())),
+        format!("Hints used: {}", hints_used.0),
+        format!("Mistakes: {}", mistakes.count()),
+    ]);
+    if let Some(stats) = statistics.get(level, **difficulty) {
+        lines.push(format!(
+            "{}x{} {}: {}",
+            level.width(),
+            level.height(),
+            **difficulty,
+            stats.summary()
+        ));
+    }
+
+    commands
+        .spawn((
+            GameComponent,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    top: Val::Px(16.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            for line in lines {
+                parent.spawn((
+                    GameComponent,
+                    TextBundle::from_section(
+                        line,
+                        TextStyle {
+                            font_size: TEXT_SIZE / 2.0,
+                            color: theme.text,
+                            ..Default::default()
+                        },
+                    ),
+                ));
+            }
+        });
 }
 
 fn despawn_game(mut commands: Commands, q_entity: Query<Entity, With<GameComponent>>) {
@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+
+use dnd_rs_level::{fnv1a, Pack, Puzzle};
+
+use crate::{
+    config::{Config, LevelSource},
+    storage::Storage,
+};
+
+const PACK_POSITION_KEY: &str = "pack_position";
+
+pub(crate) fn plugin(app: &mut App) {
+    let config = app.world().resource::<Config>();
+    let shared = config.shared_puzzle.clone();
+    let key = match &config.level_source {
+        LevelSource::Pack(pack) => position_key(pack),
+        _ => PACK_POSITION_KEY.to_string(),
+    };
+    let position = app.world().resource::<Storage>().load::<usize>(&key);
+    app.insert_resource(PackPosition {
+        key,
+        next: position.unwrap_or_default(),
+    })
+    .insert_resource(SharedPuzzle(shared));
+}
+
+/// Storage key for the position in the pack, so each pack continues where it was left
+fn position_key(pack: &Pack) -> String {
+    let hash = fnv1a(pack.to_string().as_bytes());
+    format!("{PACK_POSITION_KEY}_{hash:016x}")
+}
+
+/// Puzzle to play before anything else, for example from a share code
+#[derive(Resource)]
+pub(crate) struct SharedPuzzle(Option<Puzzle>);
+
+impl SharedPuzzle {
+    /// The shared puzzle the first time, and `None` after that
+    pub(crate) fn take(&mut self) -> Option<Puzzle> {
+        self.0.take()
+    }
+}
+
+/// Index of the next puzzle to play from the pack, saved so the pack continues after a restart
+#[derive(Resource)]
+pub(crate) struct PackPosition {
+    key: String,
+    next: usize,
+}
+
+impl PackPosition {
+    /// The next puzzle in the pack and its number, starting over after the last one
+    pub(crate) fn next_puzzle(&mut self, pack: &Pack, storage: &Storage) -> (usize, Puzzle) {
+        let index = self.next % pack.puzzles.len();
+        self.next = index + 1;
+        storage.save(&self.key, &self.next);
+
+        let puzzle = &pack.puzzles[index];
+        info!(
+            "Playing puzzle {} of {}{}",
+            index + 1,
+            pack.puzzles.len(),
+            puzzle
+                .title
+                .as_ref()
+                .map_or(String::new(), |title| format!(": {title}"))
+        );
+        (index + 1, puzzle.clone())
+    }
+}
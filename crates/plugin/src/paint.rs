@@ -0,0 +1,392 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+
+use dnd_rs_level::Mark;
+
+use crate::{
+    actions::{CellEdit, Change, PlayerAction},
+    camera, find_cell_at, stats, toolbar, AppState, BoardPointer, Cell, Column, Floor, HallwayMark,
+    QuestionMark, Row, Wall,
+};
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<PaintSettings>()
+        .init_resource::<CurrentStroke>()
+        .add_systems(OnEnter(AppState::Loading), end_stroke)
+        .add_systems(
+            Update,
+            (
+                toggle_paint_settings,
+                end_stroke.run_if(camera::is_panning),
+                (paint_with_mouse, paint_with_touch)
+                    .run_if(not(camera::is_panning))
+                    .run_if(not(toolbar::is_pointer_over_toolbar))
+                    .run_if(not(stats::is_stats_page_open)),
+            )
+                .run_if(in_state(AppState::Playing)),
+        );
+}
+
+#[derive(Resource, Default)]
+pub(crate) struct PaintSettings {
+    /// Keep every stroke in the row or column it starts in. Holding shift does the same.
+    pub(crate) straight_lines: bool,
+    pub(crate) controls: MarkControls,
+}
+
+/// Which mouse button does what
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum MarkControls {
+    /// Left click cycles between empty, wall and hallway mark. Right click toggles question marks
+    /// and middle click hallway marks.
+    #[default]
+    Cycle,
+    /// Left click toggles walls, right click hallway marks and middle click question marks
+    Buttons,
+}
+
+/// The ways a player can change a cell. Taps and space work like a left click.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Tool {
+    Primary,
+    HallwayMark,
+    QuestionMark,
+}
+
+impl Tool {
+    fn for_button(button: MouseButton, controls: MarkControls) -> Option<Self> {
+        match (button, controls) {
+            (MouseButton::Left, _) => Some(Tool::Primary),
+            (MouseButton::Right, MarkControls::Cycle) => Some(Tool::QuestionMark),
+            (MouseButton::Right, MarkControls::Buttons) => Some(Tool::HallwayMark),
+            (MouseButton::Middle, MarkControls::Cycle) => Some(Tool::HallwayMark),
+            (MouseButton::Middle, MarkControls::Buttons) => Some(Tool::QuestionMark),
+            _ => None,
+        }
+    }
+}
+
+/// The marks the player has put on the board
+#[derive(SystemParam)]
+pub(crate) struct PlayerMarks<'w, 's> {
+    q_walls: Query<'w, 's, (&'static Row, &'static Column), With<Wall>>,
+    q_hallway_marks: Query<'w, 's, (&'static Row, &'static Column), With<HallwayMark>>,
+    q_question_marks: Query<'w, 's, (&'static Row, &'static Column), With<QuestionMark>>,
+}
+
+impl PlayerMarks<'_, '_> {
+    pub(crate) fn mark(&self, (x, y): (usize, usize)) -> Mark {
+        let is_at = |(row, column): (&Row, &Column)| row.0 == y && column.0 == x;
+        if self.q_walls.iter().any(is_at) {
+            Mark::Wall
+        } else if self.q_hallway_marks.iter().any(is_at) {
+            Mark::Hallway
+        } else {
+            Mark::Empty
+        }
+    }
+
+    fn has_question_mark(&self, (x, y): (usize, usize)) -> bool {
+        self.q_question_marks
+            .iter()
+            .any(|(row, column)| row.0 == y && column.0 == x)
+    }
+
+    /// The change using a tool on a cell makes
+    pub(crate) fn change(
+        &self,
+        tool: Tool,
+        controls: MarkControls,
+        cell: (usize, usize),
+    ) -> Change {
+        match tool {
+            Tool::Primary => Change::Mark(match (controls, self.mark(cell)) {
+                (MarkControls::Cycle, Mark::Empty) => Mark::Wall,
+                (MarkControls::Cycle, Mark::Wall) => Mark::Hallway,
+                (MarkControls::Cycle, Mark::Hallway) => Mark::Empty,
+                (MarkControls::Buttons, Mark::Wall) => Mark::Empty,
+                (MarkControls::Buttons, _) => Mark::Wall,
+            }),
+            Tool::HallwayMark => Change::Mark(match self.mark(cell) {
+                Mark::Hallway => Mark::Empty,
+                _ => Mark::Hallway,
+            }),
+            Tool::QuestionMark => Change::QuestionMark(!self.has_question_mark(cell)),
+        }
+    }
+}
+
+/// What a stroke does to every cell it crosses. Decided by the cell it starts in,
+/// and only applied to cells in the same state.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PaintMode {
+    Mark { from: Mark, to: Mark },
+    QuestionMark { show: bool },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Pointer {
+    Mouse(MouseButton),
+    Touch(u64),
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Axis {
+    Row,
+    Column,
+}
+
+struct Stroke {
+    id: u32,
+    mode: PaintMode,
+    pointer: Pointer,
+    start: (usize, usize),
+    last: (usize, usize),
+    axis: Option<Axis>,
+}
+
+#[derive(Resource, Default)]
+struct CurrentStroke {
+    stroke: Option<Stroke>,
+    next_id: u32,
+}
+
+impl CurrentStroke {
+    fn start(
+        &mut self,
+        tool: Tool,
+        pointer: Pointer,
+        cell: (usize, usize),
+        settings: &PaintSettings,
+        marks: &PlayerMarks,
+        actions: &mut EventWriter<PlayerAction>,
+    ) {
+        let mode = match marks.change(tool, settings.controls, cell) {
+            Change::Mark(to) => PaintMode::Mark {
+                from: marks.mark(cell),
+                to,
+            },
+            Change::QuestionMark(show) => PaintMode::QuestionMark { show },
+        };
+
+        let id = self.next_id;
+        self.next_id = self.next_id.wrapping_add(1);
+        let stroke = self.stroke.insert(Stroke {
+            id,
+            mode,
+            pointer,
+            start: cell,
+            last: cell,
+            axis: None,
+        });
+        paint(actions, stroke, &[cell], marks);
+    }
+}
+
+fn end_stroke(mut current: ResMut<CurrentStroke>) {
+    current.stroke = None;
+}
+
+// L toggles straight lines and M the mouse controls
+fn toggle_paint_settings(keys: Res<ButtonInput<KeyCode>>, mut settings: ResMut<PaintSettings>) {
+    if keys.just_pressed(KeyCode::KeyL) {
+        settings.straight_lines = !settings.straight_lines;
+    }
+    if keys.just_pressed(KeyCode::KeyM) {
+        settings.controls = match settings.controls {
+            MarkControls::Cycle => MarkControls::Buttons,
+            MarkControls::Buttons => MarkControls::Cycle,
+        };
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn paint_with_mouse(
+    mouse_buttons: Res<ButtonInput<MouseButton>>,
+    keys: Res<ButtonInput<KeyCode>>,
+    settings: Res<PaintSettings>,
+    mut current: ResMut<CurrentStroke>,
+    mut actions: EventWriter<PlayerAction>,
+    marks: PlayerMarks,
+    q_floors: Query<(&Transform, &Row, &Column), With<Floor>>,
+    q_cells: Query<(&Transform, &Row, &Column), (With<Cell>, With<Sprite>)>,
+    pointer: BoardPointer,
+) {
+    let Some(position) = pointer.cursor_position() else {
+        // Cursor is not in camera view
+        return;
+    };
+
+    match &current.stroke {
+        Some(stroke) => {
+            let Pointer::Mouse(button) = stroke.pointer else {
+                return;
+            };
+            if !mouse_buttons.pressed(button) {
+                current.stroke = None;
+                return;
+            }
+        }
+        None => {
+            for &button in mouse_buttons.get_just_pressed() {
+                let Some(tool) = Tool::for_button(button, settings.controls) else {
+                    continue;
+                };
+                // Only floor can be marked, but question marks go anywhere
+                let cell = match tool {
+                    Tool::QuestionMark => find_cell_at(&q_cells, position),
+                    _ => find_cell_at(&q_floors, position),
+                };
+                if let Some(cell) = cell {
+                    let pointer = Pointer::Mouse(button);
+                    current.start(tool, pointer, cell, &settings, &marks, &mut actions);
+                    break;
+                }
+            }
+            return;
+        }
+    }
+
+    let straight =
+        settings.straight_lines || keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if let Some(cell) = find_cell_at(&q_cells, position) {
+        extend_stroke(&mut current, cell, straight, &mut actions, &marks);
+    }
+}
+
+// Touch strokes work like a left click
+#[allow(clippy::too_many_arguments)]
+fn paint_with_touch(
+    touches: Res<Touches>,
+    settings: Res<PaintSettings>,
+    mut current: ResMut<CurrentStroke>,
+    mut actions: EventWriter<PlayerAction>,
+    marks: PlayerMarks,
+    q_floors: Query<(&Transform, &Row, &Column), With<Floor>>,
+    q_cells: Query<(&Transform, &Row, &Column), (With<Cell>, With<Sprite>)>,
+    pointer: BoardPointer,
+) {
+    match &current.stroke {
+        Some(stroke) => {
+            let Pointer::Touch(id) = stroke.pointer else {
+                return;
+            };
+            let Some(touch) = touches.get_pressed(id) else {
+                current.stroke = None;
+                return;
+            };
+            let Some(position) = pointer.viewport_to_board(touch.position()) else {
+                return;
+            };
+            if let Some(cell) = find_cell_at(&q_cells, position) {
+                extend_stroke(
+                    &mut current,
+                    cell,
+                    settings.straight_lines,
+                    &mut actions,
+                    &marks,
+                );
+            }
+        }
+        None => {
+            let Some(touch) = touches.iter_just_pressed().next() else {
+                return;
+            };
+            let Some(position) = pointer.viewport_to_board(touch.position()) else {
+                return;
+            };
+            if let Some(cell) = find_cell_at(&q_floors, position) {
+                let pointer = Pointer::Touch(touch.id());
+                current.start(
+                    Tool::Primary,
+                    pointer,
+                    cell,
+                    &settings,
+                    &marks,
+                    &mut actions,
+                );
+            }
+        }
+    }
+}
+
+fn extend_stroke(
+    current: &mut CurrentStroke,
+    target: (usize, usize),
+    straight: bool,
+    actions: &mut EventWriter<PlayerAction>,
+    marks: &PlayerMarks,
+) {
+    let Some(stroke) = &mut current.stroke else {
+        return;
+    };
+
+    let target = if straight {
+        let (start_x, start_y) = stroke.start;
+        if stroke.axis.is_none() && target != stroke.start {
+            let dx = target.0.abs_diff(start_x);
+            let dy = target.1.abs_diff(start_y);
+            stroke.axis = Some(if dx >= dy { Axis::Row } else { Axis::Column });
+        }
+        match stroke.axis {
+            Some(Axis::Row) => (target.0, start_y),
+            Some(Axis::Column) => (start_x, target.1),
+            None => target,
+        }
+    } else {
+        target
+    };
+
+    if target == stroke.last {
+        return;
+    }
+
+    let cells = cells_between(stroke.last, target);
+    stroke.last = target;
+    paint(actions, stroke, &cells, marks);
+}
+
+// Every cell on the way from one cell to another, not including the first,
+// so fast pointer movements don't skip cells
+fn cells_between(from: (usize, usize), to: (usize, usize)) -> Vec<(usize, usize)> {
+    let dx = to.0 as f32 - from.0 as f32;
+    let dy = to.1 as f32 - from.1 as f32;
+    let steps = dx.abs().max(dy.abs()) as usize;
+
+    (1..=steps)
+        .map(|i| {
+            let t = i as f32 / steps as f32;
+            (
+                (from.0 as f32 + dx * t).round() as usize,
+                (from.1 as f32 + dy * t).round() as usize,
+            )
+        })
+        .collect()
+}
+
+fn paint(
+    actions: &mut EventWriter<PlayerAction>,
+    stroke: &Stroke,
+    cells: &[(usize, usize)],
+    marks: &PlayerMarks,
+) {
+    let edits: Vec<CellEdit> = cells
+        .iter()
+        .filter_map(|&(x, y)| {
+            let change = match stroke.mode {
+                PaintMode::Mark { from, to } if marks.mark((x, y)) == from => Change::Mark(to),
+                PaintMode::QuestionMark { show } if marks.has_question_mark((x, y)) != show => {
+                    Change::QuestionMark(show)
+                }
+                _ => return None,
+            };
+            Some(CellEdit { x, y, change })
+        })
+        .collect();
+
+    if !edits.is_empty() {
+        actions.send(PlayerAction {
+            edits,
+            stroke: Some(stroke.id),
+        });
+    }
+}
@@ -0,0 +1,251 @@
+use std::time::Duration;
+
+use bevy::{
+    ecs::{query::QueryFilter, system::SystemParam},
+    prelude::*,
+    window::WindowFocused,
+};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use dnd_rs_level::{Level, Mark, Puzzle};
+
+use crate::{
+    actions::find_cell, check::Mistakes, config::LevelSource, daily::GameMode, hint::HintsUsed,
+    spawn_hallway_mark, spawn_question_mark, spawn_wall, stats::SolveTimer, storage::Storage,
+    theme::Theme, AppState, Cell, Column, Floor, HallwayMark, QuestionMark, Row, Wall,
+};
+
+const PROGRESS_KEY: &str = "progress";
+
+pub(crate) fn plugin(app: &mut App) {
+    let progress = app
+        .world()
+        .resource::<Storage>()
+        .load::<Option<SavedProgress>>(PROGRESS_KEY);
+    app.insert_resource(ResumedProgress(progress.flatten()))
+        .init_resource::<AbandonedProgress>()
+        .add_systems(OnEnter(AppState::Playing), restore_progress)
+        .add_systems(OnExit(AppState::Playing), keep_abandoned_progress)
+        .add_systems(OnEnter(AppState::Won), clear_progress)
+        .add_systems(Update, save_progress.run_if(in_state(AppState::Playing)));
+}
+
+/// The level being played and the player's marks, so it can be resumed after a restart
+#[derive(Serialize, Deserialize)]
+pub(crate) struct SavedProgress {
+    level: String,
+    daily: Option<NaiveDate>,
+    walls: Vec<(usize, usize)>,
+    hallway_marks: Vec<(usize, usize)>,
+    question_marks: Vec<(usize, usize)>,
+    seconds: f32,
+    hints_used: usize,
+    #[serde(default)]
+    wrong_walls: Vec<(usize, usize)>,
+    #[serde(default)]
+    wrong_hallway_marks: Vec<(usize, usize)>,
+}
+
+/// Progress saved in the last session, played instead of a new level on startup
+#[derive(Resource)]
+pub(crate) struct ResumedProgress(pub(crate) Option<SavedProgress>);
+
+impl ResumedProgress {
+    /// The saved level, if it is one the level source could give. Daily levels are always
+    /// resumed, but a host asking for a specific level or pack doesn't get another one.
+    pub(crate) fn level_for(&mut self, source: &LevelSource) -> Option<(Level, GameMode)> {
+        let (level, mode) = self.level()?;
+        let resumable = match (source, mode) {
+            (_, GameMode::Daily(_)) => true,
+            (LevelSource::Random { .. } | LevelSource::FitWindow, _) => true,
+            (LevelSource::Fixed(fixed), _) => *fixed == level,
+            (LevelSource::Pack(pack), _) => {
+                let puzzle = Puzzle::from_level(&level);
+                pack.puzzles.iter().any(|p| p.has_same_board(&puzzle))
+            }
+            (LevelSource::Custom(_), _) => false,
+        };
+        if !resumable {
+            info!("Ignoring saved progress for a level the level source doesn't give");
+            self.0 = None;
+            return None;
+        }
+        Some((level, mode))
+    }
+
+    pub(crate) fn level(&mut self) -> Option<(Level, GameMode)> {
+        let progress = self.0.as_ref()?;
+        match Level::try_from(progress.level.as_str()) {
+            Ok(level) => Some((
+                level,
+                progress.daily.map_or(GameMode::Random, GameMode::Daily),
+            )),
+            Err(err) => {
+                warn!("Ignoring saved progress: {err}");
+                self.0 = None;
+                None
+            }
+        }
+    }
+}
+
+/// The game left for a new level, so it can be resumed when generating that level is
+/// cancelled. Cleared when the game is won instead.
+#[derive(Resource, Default)]
+pub(crate) struct AbandonedProgress(Option<SavedProgress>);
+
+impl AbandonedProgress {
+    pub(crate) fn exists(&self) -> bool {
+        self.0.is_some()
+    }
+
+    /// Makes the abandoned game the next one to resume, and returns its level
+    pub(crate) fn resume(&mut self, resumed: &mut ResumedProgress) -> Option<(Level, GameMode)> {
+        resumed.0 = self.0.take();
+        resumed.level()
+    }
+}
+
+/// The level being played and the player's marks, as they are now
+#[derive(SystemParam)]
+struct CurrentProgress<'w, 's> {
+    game_mode: Res<'w, GameMode>,
+    timer: Res<'w, SolveTimer>,
+    hints_used: Res<'w, HintsUsed>,
+    mistakes: Res<'w, Mistakes>,
+    q_level: Query<'w, 's, &'static Level>,
+    q_walls: Query<'w, 's, (&'static Row, &'static Column), With<Wall>>,
+    q_hallway_marks: Query<'w, 's, (&'static Row, &'static Column), With<HallwayMark>>,
+    q_question_marks: Query<'w, 's, (&'static Row, &'static Column), With<QuestionMark>>,
+}
+
+impl CurrentProgress<'_, '_> {
+    fn get(&self) -> Option<SavedProgress> {
+        Some(SavedProgress {
+            level: format!("{:?}", self.q_level.get_single().ok()?),
+            daily: match *self.game_mode {
+                GameMode::Random => None,
+                GameMode::Daily(date) => Some(date),
+            },
+            walls: positions(&self.q_walls),
+            hallway_marks: positions(&self.q_hallway_marks),
+            question_marks: positions(&self.q_question_marks),
+            seconds: self.timer.elapsed_secs(),
+            hints_used: self.hints_used.0,
+            wrong_walls: self.mistakes_of(Mark::Wall),
+            wrong_hallway_marks: self.mistakes_of(Mark::Hallway),
+        })
+    }
+
+    fn mistakes_of(&self, mark: Mark) -> Vec<(usize, usize)> {
+        self.mistakes
+            .0
+            .iter()
+            .filter(|&&(_, _, m)| m == mark)
+            .map(|&(x, y, _)| (x, y))
+            .collect()
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn restore_progress(
+    mut commands: Commands,
+    mut resumed: ResMut<ResumedProgress>,
+    mut timer: ResMut<SolveTimer>,
+    mut hints_used: ResMut<HintsUsed>,
+    mut mistakes: ResMut<Mistakes>,
+    theme: Res<Theme>,
+    q_floors: Query<(&Transform, &Row, &Column), With<Floor>>,
+    q_cells: Query<(&Transform, &Row, &Column), (With<Cell>, With<Sprite>)>,
+) {
+    let Some(progress) = resumed.0.take() else {
+        return;
+    };
+    info!("Resuming saved progress");
+
+    for (translation, row, column) in progress
+        .walls
+        .iter()
+        .filter_map(|&p| find_cell(&q_floors, p))
+    {
+        spawn_wall(&mut commands, &theme, translation, row, column);
+    }
+    for (translation, row, column) in progress
+        .hallway_marks
+        .iter()
+        .filter_map(|&p| find_cell(&q_floors, p))
+    {
+        spawn_hallway_mark(&mut commands, &theme, translation, row, column);
+    }
+    for (translation, row, column) in progress
+        .question_marks
+        .iter()
+        .filter_map(|&p| find_cell(&q_cells, p))
+    {
+        spawn_question_mark(&mut commands, &theme, translation, row, column);
+    }
+
+    timer.set_elapsed(Duration::from_secs_f32(progress.seconds));
+    hints_used.0 = progress.hints_used;
+    mistakes.0.extend(
+        (progress
+            .wrong_walls
+            .iter()
+            .map(|&(x, y)| (x, y, Mark::Wall)))
+        .chain(
+            progress
+                .wrong_hallway_marks
+                .iter()
+                .map(|&(x, y)| (x, y, Mark::Hallway)),
+        ),
+    );
+}
+
+// Saves whenever the board changes, and when the game loses focus to keep the time
+fn save_progress(
+    storage: Res<Storage>,
+    mut focus_events: EventReader<WindowFocused>,
+    mut removed_walls: RemovedComponents<Wall>,
+    mut removed_hallway_marks: RemovedComponents<HallwayMark>,
+    mut removed_question_marks: RemovedComponents<QuestionMark>,
+    q_changed: Query<
+        (),
+        Or<(
+            Added<Level>,
+            Added<Wall>,
+            Added<HallwayMark>,
+            Added<QuestionMark>,
+        )>,
+    >,
+    current: CurrentProgress,
+) {
+    let focus_lost = focus_events.read().any(|event| !event.focused);
+    let removed = removed_walls.read().count()
+        + removed_hallway_marks.read().count()
+        + removed_question_marks.read().count()
+        > 0;
+    if !focus_lost && !removed && q_changed.is_empty() {
+        return;
+    }
+
+    if let Some(progress) = current.get() {
+        storage.save(PROGRESS_KEY, &Some(progress));
+    }
+}
+
+fn keep_abandoned_progress(mut abandoned: ResMut<AbandonedProgress>, current: CurrentProgress) {
+    abandoned.0 = current.get();
+}
+
+fn positions<F: QueryFilter>(query: &Query<(&Row, &Column), F>) -> Vec<(usize, usize)> {
+    query
+        .iter()
+        .map(|(row, column)| (column.0, row.0))
+        .collect()
+}
+
+fn clear_progress(storage: Res<Storage>, mut abandoned: ResMut<AbandonedProgress>) {
+    storage.save(PROGRESS_KEY, &None::<SavedProgress>);
+    abandoned.0 = None;
+}
@@ -0,0 +1,113 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    task::Poll,
+};
+
+use bevy::{prelude::*, window::PrimaryWindow};
+
+use dnd_rs_level::{Difficulty, Level, LevelBuilder};
+
+use crate::{
+    config::{Config, LevelSource},
+    generation::{PendingLevel, BUILDS_ON_MAIN_THREAD},
+    layout, AppState,
+};
+
+/// Board size for random levels when there is nothing to fit them to
+pub(crate) const FALLBACK_LEVEL_SIZE: (usize, usize) = (8, 8);
+
+pub(crate) fn plugin(app: &mut App) {
+    app.init_resource::<PuzzleQueue>();
+
+    // Generating while playing would make the game stutter where it can't be done on
+    // another thread
+    if !BUILDS_ON_MAIN_THREAD {
+        app.add_systems(
+            Update,
+            refill_queue.run_if(not(in_state(AppState::Loading))),
+        );
+    }
+}
+
+/// Random levels generated ahead of time for each board size, so the next game starts
+/// right away. Refilled while a game is played, not while one is loading.
+#[derive(Resource, Default)]
+pub(crate) struct PuzzleQueue {
+    ready: HashMap<(usize, usize), VecDeque<(Level, Difficulty)>>,
+    /// Levels still being generated. The queue only starts one when there are none, but
+    /// cancelled levels are added on top. Each one's solver can still take every core.
+    generating: Vec<((usize, usize), PendingLevel)>,
+}
+
+pub(crate) enum QueuedLevel {
+    Ready((Level, Difficulty)),
+    Generating(PendingLevel),
+}
+
+impl PuzzleQueue {
+    /// A ready level of the size, or the one being generated for it
+    pub(crate) fn take(&mut self, size: (usize, usize)) -> Option<QueuedLevel> {
+        if let Some(level) = self.ready.get_mut(&size).and_then(VecDeque::pop_front) {
+            return Some(QueuedLevel::Ready(level));
+        }
+        let index = self.generating.iter().position(|(s, _)| *s == size)?;
+        let (_, pending) = self.generating.remove(index);
+        Some(QueuedLevel::Generating(pending))
+    }
+
+    /// Waits for the level, instead of starting another one of the size
+    pub(crate) fn add_generating(&mut self, size: (usize, usize), pending: PendingLevel) {
+        self.generating.push((size, pending));
+    }
+
+    pub(crate) fn ready_count(&self, size: (usize, usize)) -> usize {
+        self.ready.get(&size).map_or(0, VecDeque::len)
+    }
+}
+
+/// Board size of the next random level, for level sources that give random levels
+pub(crate) fn random_level_size(
+    config: &Config,
+    window: Option<&Window>,
+) -> Option<(usize, usize)> {
+    match &config.level_source {
+        LevelSource::Random { width, height } => Some((*width, *height)),
+        LevelSource::FitWindow => {
+            Some(window.map_or(FALLBACK_LEVEL_SIZE, layout::fitting_board_size))
+        }
+        _ => None,
+    }
+}
+
+pub(crate) fn random_level_builder((width, height): (usize, usize)) -> LevelBuilder {
+    Level::builder(width, height)
+        .check_too_many_walls()
+        .check_unique_solution()
+}
+
+fn refill_queue(
+    mut queue: ResMut<PuzzleQueue>,
+    config: Res<Config>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let queue = &mut *queue;
+    queue.generating.retain(|(size, pending)| {
+        match pending.poll() {
+            Poll::Pending => return true,
+            Poll::Ready(Ok(level)) => queue.ready.entry(*size).or_default().push_back(level),
+            Poll::Ready(Err(err)) => warn!("Could not generate a level ahead of time: {err}"),
+        }
+        false
+    });
+    if !queue.generating.is_empty() {
+        return;
+    }
+
+    let Some(size) = random_level_size(&config, q_windows.get_single().ok()) else {
+        return;
+    };
+    if queue.ready_count(size) < config.queued_puzzles {
+        info!("Generating a {}x{} level ahead of time", size.0, size.1);
+        queue.add_generating(size, PendingLevel::start(random_level_builder(size)));
+    }
+}
@@ -0,0 +1,263 @@
+use std::collections::BTreeMap;
+
+use bevy::{prelude::*, time::Stopwatch, window::PrimaryWindow};
+use serde::{Deserialize, Serialize};
+
+use dnd_rs_level::{Difficulty, Level};
+
+use crate::{
+    check::Mistakes,
+    hint::HintsUsed,
+    storage::Storage,
+    theme::Theme,
+    toolbar::{ToolbarButton, ToolbarPressed},
+    AppState, GameComponent,
+};
+
+const STATISTICS_KEY: &str = "statistics";
+
+const STATS_TEXT_SIZE: f32 = 24.0;
+
+pub(crate) fn plugin(app: &mut App) {
+    let statistics = app
+        .world()
+        .resource::<Storage>()
+        .load::<Statistics>(STATISTICS_KEY);
+    app.init_resource::<SolveTimer>()
+        .init_resource::<PuzzleDifficulty>()
+        .insert_resource(statistics.unwrap_or_default())
+        .add_systems(OnEnter(AppState::Loading), reset_timer)
+        .add_systems(OnExit(AppState::Loading), spawn_timer_text)
+        .add_systems(OnExit(AppState::Playing), despawn_stats_page)
+        .add_systems(OnEnter(AppState::Won), record_solve)
+        .add_systems(
+            Update,
+            (toggle_stats_page, tick_timer, update_timer_text)
+                .chain()
+                .run_if(in_state(AppState::Playing)),
+        );
+}
+
+/// Time spent solving the current level, not counting time the game was out of focus
+#[derive(Resource, Default, Deref, DerefMut)]
+pub(crate) struct SolveTimer(Stopwatch);
+
+/// Rated while the level is generated, to keep the search off the main thread
+#[derive(Resource, Deref)]
+pub(crate) struct PuzzleDifficulty(pub(crate) Difficulty);
+
+impl Default for PuzzleDifficulty {
+    fn default() -> Self {
+        Self(Difficulty::Easy)
+    }
+}
+
+/// Results of every solved level, grouped by board size and difficulty
+#[derive(Resource, Default, Serialize, Deserialize)]
+pub(crate) struct Statistics(BTreeMap<String, Stats>);
+
+impl Statistics {
+    pub(crate) fn get(&self, level: &Level, difficulty: Difficulty) -> Option<&Stats> {
+        self.0.get(&Self::key(level, difficulty))
+    }
+
+    fn key(level: &Level, difficulty: Difficulty) -> String {
+        format!("{}x{} {}", level.width(), level.height(), difficulty)
+    }
+}
+
+#[derive(Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Stats {
+    solved: u32,
+    best_time: Option<f32>,
+    total_time: f32,
+    hints: u32,
+    mistakes: u32,
+    /// Levels solved in a row without hints
+    streak: u32,
+    best_streak: u32,
+}
+
+impl Stats {
+    fn record(&mut self, seconds: f32, hints: usize, mistakes: usize) {
+        self.solved += 1;
+        self.best_time = Some(self.best_time.map_or(seconds, |best| best.min(seconds)));
+        self.total_time += seconds;
+        self.hints += hints as u32;
+        self.mistakes += mistakes as u32;
+        self.streak = if hints == 0 { self.streak + 1 } else { 0 };
+        self.best_streak = self.best_streak.max(self.streak);
+    }
+
+    fn average_time(&self) -> f32 {
+        self.total_time / self.solved.max(1) as f32
+    }
+
+    pub(crate) fn summary(&self) -> String {
+        format!(
+            "{} solved, best {}, average {}, {} hints, {} mistakes, streak {} (best {})",
+            self.solved,
+            format_time(self.best_time.unwrap_or_default()),
+            format_time(self.average_time()),
+            self.hints,
+            self.mistakes,
+            self.streak,
+            self.best_streak,
+        )
+    }
+}
+
+pub(crate) fn format_time(seconds: f32) -> String {
+    let seconds = seconds as u32;
+    match seconds / 3600 {
+        0 => format!("{}:{:02}", seconds / 60, seconds % 60),
+        hours => format!("{}:{:02}:{:02}", hours, seconds / 60 % 60, seconds % 60),
+    }
+}
+
+#[derive(Component)]
+pub(crate) struct TimerText;
+
+#[derive(Component)]
+pub(crate) struct StatsPage;
+
+/// Run condition for world input, so the board can't be changed behind the stats page
+pub(crate) fn is_stats_page_open(q_page: Query<(), With<StatsPage>>) -> bool {
+    !q_page.is_empty()
+}
+
+fn reset_timer(mut timer: ResMut<SolveTimer>) {
+    timer.reset();
+    timer.unpause();
+}
+
+fn spawn_timer_text(mut commands: Commands, theme: Res<Theme>) {
+    commands.spawn((
+        GameComponent,
+        TimerText,
+        TextBundle::from_section(
+            format_time(0.0),
+            TextStyle {
+                font_size: STATS_TEXT_SIZE,
+                color: theme.text,
+                ..Default::default()
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..Default::default()
+        }),
+    ));
+}
+
+fn tick_timer(
+    time: Res<Time>,
+    mut timer: ResMut<SolveTimer>,
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    q_page: Query<(), With<StatsPage>>,
+) {
+    let focused = q_windows.iter().all(|window| window.focused);
+    if focused && q_page.is_empty() {
+        timer.tick(time.delta());
+    }
+}
+
+fn update_timer_text(timer: Res<SolveTimer>, mut q_text: Query<&mut Text, With<TimerText>>) {
+    for mut text in &mut q_text {
+        text.sections[0].value = format_time(timer.elapsed_secs());
+    }
+}
+
+pub(crate) fn record_solve(
+    storage: Res<Storage>,
+    timer: Res<SolveTimer>,
+    hints_used: Res<HintsUsed>,
+    mistakes: Res<Mistakes>,
+    difficulty: Res<PuzzleDifficulty>,
+    mut statistics: ResMut<Statistics>,
+    q_level: Query<&Level>,
+) {
+    let key = Statistics::key(q_level.single(), **difficulty);
+    statistics.0.entry(key).or_default().record(
+        timer.elapsed_secs(),
+        hints_used.0,
+        mistakes.count(),
+    );
+    storage.save(STATISTICS_KEY, &*statistics);
+}
+
+fn toggle_stats_page(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mut toolbar_events: EventReader<ToolbarPressed>,
+    statistics: Res<Statistics>,
+    q_page: Query<Entity, With<StatsPage>>,
+) {
+    let pressed = toolbar_events
+        .read()
+        .filter(|event| ***event == ToolbarButton::Stats)
+        .count()
+        > 0;
+    if !pressed && !keys.just_pressed(KeyCode::Tab) {
+        return;
+    }
+
+    if !q_page.is_empty() {
+        for entity in &q_page {
+            commands.entity(entity).despawn_recursive();
+        }
+        return;
+    }
+
+    let text_style = TextStyle {
+        font_size: STATS_TEXT_SIZE,
+        color: theme.text,
+        ..Default::default()
+    };
+
+    let mut lines = vec!["Statistics".to_string()];
+    if statistics.0.is_empty() {
+        lines.push("No levels solved yet".to_string());
+    }
+    for (key, stats) in &statistics.0 {
+        lines.push(format!("{key}: {}", stats.summary()));
+    }
+
+    commands
+        .spawn((
+            GameComponent,
+            StatsPage,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(8.0),
+                    ..Default::default()
+                },
+                background_color: theme.page.into(),
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            for line in lines {
+                parent.spawn((
+                    GameComponent,
+                    TextBundle::from_section(line, text_style.clone()),
+                ));
+            }
+        });
+}
+
+fn despawn_stats_page(mut commands: Commands, q_page: Query<Entity, With<StatsPage>>) {
+    for entity in &q_page {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,96 @@
+//! Small key-value store for data that should survive restarts.
+//!
+//! Values are JSON files in the platform data directory on desktop and Android,
+//! and entries in local storage on the web.
+
+use bevy::{log::warn, prelude::Resource};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Handle to the store. Without persistence nothing is loaded or saved.
+#[derive(Resource, Clone, Copy)]
+pub(crate) struct Storage {
+    pub(crate) persistent: bool,
+}
+
+impl Storage {
+    pub(crate) fn load<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        if !self.persistent {
+            return None;
+        }
+        load(key)
+    }
+
+    pub(crate) fn save<T: Serialize>(&self, key: &str, value: &T) {
+        if self.persistent {
+            save(key, value);
+        }
+    }
+}
+
+fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let value = read(key)?;
+    match serde_json::from_str(&value) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            warn!("Ignoring stored {key}: {err}");
+            None
+        }
+    }
+}
+
+fn save<T: Serialize>(key: &str, value: &T) {
+    let value = match serde_json::to_string(value) {
+        Ok(value) => value,
+        Err(err) => {
+            warn!("Could not serialize {key}: {err}");
+            return;
+        }
+    };
+    if let Err(err) = write(key, &value) {
+        warn!("Could not store {key}: {err}");
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn path(key: &str) -> Option<std::path::PathBuf> {
+    #[cfg(target_os = "android")]
+    let dir = bevy::winit::ANDROID_APP.get()?.internal_data_path()?;
+    #[cfg(not(target_os = "android"))]
+    let dir = directories::ProjectDirs::from("dk", "bjarkebjarke", "dnd")?
+        .data_dir()
+        .to_path_buf();
+
+    Some(dir.join(format!("{key}.json")))
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read(key: &str) -> Option<String> {
+    std::fs::read_to_string(path(key)?).ok()
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write(key: &str, value: &str) -> Result<(), String> {
+    let path = path(key).ok_or("no data directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    std::fs::write(path, value).map_err(|err| err.to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> Option<web_sys::Storage> {
+    web_sys::window()?.local_storage().ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read(key: &str) -> Option<String> {
+    local_storage()?.get_item(&format!("dnd.{key}")).ok()?
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write(key: &str, value: &str) -> Result<(), String> {
+    local_storage()
+        .ok_or("no local storage")?
+        .set_item(&format!("dnd.{key}"), value)
+        .map_err(|err| format!("{err:?}"))
+}
@@ -0,0 +1,568 @@
+use std::{cmp::Ordering, time::Duration};
+
+use bevy::{
+    input::{
+        keyboard::{Key, KeyboardInput},
+        ButtonState, InputPlugin,
+    },
+    state::app::StatesPlugin,
+    window::{ExitCondition, WindowPlugin},
+};
+
+use dnd_rs_level::Solver;
+
+use super::*;
+
+const LEVEL: &str = r#"
+M#M#####
+...M####
+#.##..T#
+#.M#...#
+M.##...#
+#..M#.#M
+M#.##...
+......#M
+"#;
+
+// Frames to wait for the level images to load
+const MAX_LOADING_FRAMES: usize = 1000;
+
+fn level() -> Level {
+    Level::try_from(LEVEL).unwrap()
+}
+
+// The game without a window or renderer, playing `level` every game
+fn headless_app(level: Level) -> App {
+    headless_app_with(DungeonsAndDiagramsPlugin::default().with_level(level))
+}
+
+fn headless_app_with(plugin: DungeonsAndDiagramsPlugin) -> App {
+    headless_app_with_camera(plugin.without_camera())
+}
+
+fn headless_app_with_camera(plugin: DungeonsAndDiagramsPlugin) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins,
+        AssetPlugin {
+            file_path: "../../assets".to_string(),
+            ..Default::default()
+        },
+        ImagePlugin::default(),
+        InputPlugin,
+        WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            close_when_requested: false,
+        },
+        TransformPlugin,
+        HierarchyPlugin,
+        StatesPlugin,
+        plugin.without_win_screen().without_persistence(),
+    ))
+    .init_resource::<Recorded<PuzzleStarted>>()
+    .init_resource::<Recorded<PuzzleSolved>>()
+    .init_resource::<Recorded<CellChanged>>()
+    .add_systems(
+        Last,
+        (
+            record::<PuzzleStarted>,
+            record::<PuzzleSolved>,
+            record::<CellChanged>,
+        ),
+    );
+    app.finish();
+    app.cleanup();
+    app
+}
+
+fn state(app: &App) -> AppState {
+    *app.world().resource::<State<AppState>>().get()
+}
+
+// Runs until the given number of puzzles have started
+fn wait_for_puzzles(app: &mut App, count: usize) {
+    for _ in 0..MAX_LOADING_FRAMES {
+        app.update();
+        if events::<PuzzleStarted>(app).len() == count {
+            assert_eq!(state(app), AppState::Playing);
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    panic!("Level did not finish loading");
+}
+
+fn started_app() -> App {
+    let mut app = headless_app(level());
+    wait_for_puzzles(&mut app, 1);
+    app
+}
+
+fn set_cell(app: &mut App, x: usize, y: usize, mark: Mark) {
+    app.world_mut().send_event(SetCell { x, y, mark });
+    app.update();
+}
+
+fn walls(level: &Level) -> Vec<(usize, usize)> {
+    level
+        .iter()
+        .filter(|c| c.has_wall())
+        .map(|c| (c.x(), c.y()))
+        .collect()
+}
+
+fn row_status(app: &mut App, y: usize) -> Ordering {
+    app.world_mut()
+        .query::<(&Row, &HeaderStatus)>()
+        .iter(app.world())
+        .find(|(row, _)| row.0 == y)
+        .map(|(_, status)| status.0)
+        .unwrap()
+}
+
+// Events sent since the app started, as they are only kept for two frames
+#[derive(Resource)]
+struct Recorded<E>(Vec<E>);
+
+impl<E> Default for Recorded<E> {
+    fn default() -> Self {
+        Self(Vec::new())
+    }
+}
+
+fn record<E: Event + Clone>(mut events: EventReader<E>, mut recorded: ResMut<Recorded<E>>) {
+    recorded.0.extend(events.read().cloned());
+}
+
+fn events<E: Event + Clone>(app: &App) -> Vec<E> {
+    app.world().resource::<Recorded<E>>().0.clone()
+}
+
+// Lets commands, state transitions and systems reacting to them run
+fn settle(app: &mut App) {
+    for _ in 0..3 {
+        app.update();
+    }
+}
+
+#[test]
+fn test_camera_centred_on_transformed_board() {
+    let transform = Transform::from_xyz(500.0, -200.0, 0.0).with_scale(Vec3::splat(0.5));
+    let mut app = headless_app_with_camera(
+        DungeonsAndDiagramsPlugin::default()
+            .with_level(level())
+            .with_transform(transform),
+    );
+    wait_for_puzzles(&mut app, 1);
+    settle(&mut app);
+
+    let size = Vec2::new(
+        9.0 * UNIT_SIZE + PADDING_LEFT + PADDING_RIGHT,
+        9.0 * UNIT_SIZE + PADDING_TOP + PADDING_BOTTOM,
+    );
+    let center = transform.transform_point((size / 2.0).extend(0.0)).xy();
+    let camera = app
+        .world_mut()
+        .query_filtered::<&GlobalTransform, With<MainCamera>>()
+        .single(app.world())
+        .translation()
+        .xy();
+    assert!(camera.distance(center) < 0.01, "{camera} != {center}");
+}
+
+#[test]
+fn test_puzzle_started() {
+    let app = started_app();
+
+    let started = events::<PuzzleStarted>(&app);
+    assert_eq!(started.len(), 1);
+    assert!(started[0].level == level());
+    assert_eq!(
+        **app.world().resource::<stats::PuzzleDifficulty>(),
+        Solver::from_level(&level()).difficulty()
+    );
+}
+
+#[test]
+fn test_solving_wins() {
+    let mut app = started_app();
+
+    let walls = walls(&level());
+    let (last, rest) = walls.split_last().unwrap();
+    for &(x, y) in rest {
+        set_cell(&mut app, x, y, Mark::Wall);
+    }
+    settle(&mut app);
+    assert_eq!(state(&app), AppState::Playing);
+
+    set_cell(&mut app, last.0, last.1, Mark::Wall);
+    settle(&mut app);
+    assert_eq!(state(&app), AppState::Won);
+
+    let solved = events::<PuzzleSolved>(&app);
+    assert_eq!(solved.len(), 1);
+    assert_eq!(solved[0].hints, 0);
+
+    // Any key starts the next game, also without the win screen
+    for _ in 0..12 {
+        std::thread::sleep(Duration::from_millis(100));
+        app.update();
+    }
+    app.world_mut().send_event(KeyboardInput {
+        key_code: KeyCode::Space,
+        logical_key: Key::Space,
+        state: ButtonState::Pressed,
+        window: Entity::PLACEHOLDER,
+    });
+    wait_for_puzzles(&mut app, 2);
+}
+
+#[test]
+fn test_wrong_walls_do_not_win() {
+    let mut app = started_app();
+    let level = level();
+
+    // One wall too many
+    let floor = level
+        .iter()
+        .find(|c| *c.kind() == CellKind::Floor(CellFloor::Empty))
+        .unwrap();
+    set_cell(&mut app, floor.x(), floor.y(), Mark::Wall);
+    for &(x, y) in &walls(&level) {
+        set_cell(&mut app, x, y, Mark::Wall);
+    }
+    settle(&mut app);
+
+    assert_eq!(state(&app), AppState::Playing);
+}
+
+#[test]
+fn test_other_solution_wins() {
+    let solution = Level::try_from("M#..M\n...##\n##...\nM..#M").unwrap();
+    let mut app = headless_app(solution);
+    wait_for_puzzles(&mut app, 1);
+
+    // The same puzzle mirrored top to bottom, a different solution than the level's
+    let other = Level::try_from("M..#M\n##...\n...##\nM#..M").unwrap();
+    for &(x, y) in &walls(&other) {
+        set_cell(&mut app, x, y, Mark::Wall);
+    }
+    settle(&mut app);
+
+    assert_eq!(state(&app), AppState::Won);
+}
+
+#[test]
+fn test_header_status() {
+    let mut app = started_app();
+
+    // Row 1 is "...M####", with four walls
+    assert_eq!(row_status(&mut app, 1), Ordering::Less);
+
+    for x in 4..8 {
+        set_cell(&mut app, x, 1, Mark::Wall);
+    }
+    settle(&mut app);
+    assert_eq!(row_status(&mut app, 1), Ordering::Equal);
+
+    set_cell(&mut app, 0, 1, Mark::Wall);
+    settle(&mut app);
+    assert_eq!(row_status(&mut app, 1), Ordering::Greater);
+
+    set_cell(&mut app, 0, 1, Mark::Hallway);
+    settle(&mut app);
+    assert_eq!(row_status(&mut app, 1), Ordering::Equal);
+}
+
+#[test]
+fn test_theme_recolours_buttons() {
+    let mut app = started_app();
+    app.world_mut()
+        .send_event(toolbar::ToolbarPressed(toolbar::ToolbarButton::Theme));
+    settle(&mut app);
+
+    let theme = theme::Theme::new(theme::ThemeName::Light);
+    let (background, children) = app
+        .world_mut()
+        .query_filtered::<(&BackgroundColor, &Children), With<toolbar::ToolbarButton>>()
+        .iter(app.world())
+        .next()
+        .map(|(background, children)| (background.0, children[0]))
+        .unwrap();
+    assert_eq!(background, theme.button);
+    let text = app.world().get::<Text>(children).unwrap();
+    assert_eq!(text.sections[0].style.color, theme.button_text);
+}
+
+#[test]
+fn test_undo() {
+    let mut app = started_app();
+
+    set_cell(&mut app, 1, 0, Mark::Wall);
+    set_cell(&mut app, 1, 0, Mark::Hallway);
+    app.world_mut().send_event(Undo);
+    settle(&mut app);
+
+    let walls: Vec<(usize, usize)> = app
+        .world_mut()
+        .query_filtered::<(&Row, &Column), With<Wall>>()
+        .iter(app.world())
+        .map(|(row, column)| (column.0, row.0))
+        .collect();
+    assert_eq!(walls, vec![(1, 0)]);
+
+    let changes = events::<CellChanged>(&app);
+    let change = |from, to| CellChanged {
+        x: 1,
+        y: 0,
+        from,
+        to,
+    };
+    assert_eq!(
+        changes,
+        vec![
+            change(Mark::Empty, Mark::Wall),
+            change(Mark::Wall, Mark::Hallway),
+            change(Mark::Hallway, Mark::Wall),
+        ]
+    );
+}
+
+#[test]
+fn test_new_puzzle() {
+    let mut app = started_app();
+    set_cell(&mut app, 1, 0, Mark::Wall);
+
+    app.world_mut().send_event(NewPuzzle);
+    wait_for_puzzles(&mut app, 2);
+    let walls = app
+        .world_mut()
+        .query_filtered::<(), With<Wall>>()
+        .iter(app.world())
+        .count();
+    assert_eq!(walls, 0);
+}
+
+#[test]
+fn test_pack() {
+    let second = Level::random_with_seed(6, 6, 1).unwrap();
+    let mut puzzle = Puzzle::from_level(&second);
+    puzzle.solution = None;
+    let pack = Pack {
+        puzzles: vec![Puzzle::from_level(&level()), puzzle],
+    };
+    let mut app = headless_app_with(DungeonsAndDiagramsPlugin::default().with_pack(pack));
+
+    wait_for_puzzles(&mut app, 1);
+    app.world_mut().send_event(NewPuzzle);
+    wait_for_puzzles(&mut app, 2);
+    app.world_mut().send_event(NewPuzzle);
+    wait_for_puzzles(&mut app, 3);
+
+    let levels: Vec<Level> = events::<PuzzleStarted>(&app)
+        .into_iter()
+        .map(|event| event.level)
+        .collect();
+    assert!(levels[0] == level());
+    assert_eq!(levels[1].width(), 6);
+    assert!(Solver::from_level(&second)
+        .all_solutions()
+        .contains(&levels[1]));
+    assert!(levels[2] == level());
+}
+
+#[test]
+fn test_unsolvable_pack() {
+    // More walls in the first row than the columns allow
+    let puzzle = Puzzle::new(vec![3, 0, 0], vec![0, 0, 0], &[], &[]).unwrap();
+    let pack = Pack {
+        puzzles: vec![puzzle],
+    };
+    let mut app = headless_app_with(DungeonsAndDiagramsPlugin::default().with_pack(pack));
+
+    // A random level is played instead
+    wait_for_puzzles(&mut app, 1);
+    let started = events::<PuzzleStarted>(&app);
+    assert_eq!(started[0].level.width(), queue::FALLBACK_LEVEL_SIZE.0);
+}
+
+#[test]
+fn test_shared_puzzle() {
+    let shared = Level::random_with_seed(7, 5, 2).unwrap();
+    let mut puzzle = Puzzle::from_level(&shared);
+    puzzle.seed = Some(2);
+    let puzzle = Puzzle::from_share_code(&puzzle.to_share_code()).unwrap();
+    let mut app = headless_app_with(
+        DungeonsAndDiagramsPlugin::default()
+            .with_level(level())
+            .with_shared_puzzle(puzzle),
+    );
+
+    wait_for_puzzles(&mut app, 1);
+    app.world_mut().send_event(NewPuzzle);
+    wait_for_puzzles(&mut app, 2);
+
+    let started = events::<PuzzleStarted>(&app);
+    assert!(started[0].level == shared);
+    assert!(started[1].level == level());
+}
+
+// Progress as saved for `level`, with a wrong wall at (0, 1)
+fn saved_progress(level: &Level) -> progress::ResumedProgress {
+    let json = serde_json::json!({
+        "level": format!("{level:?}"),
+        "daily": null,
+        "walls": [[0, 1]],
+        "hallway_marks": [],
+        "question_marks": [],
+        "seconds": 5.0,
+        "hints_used": 0,
+        "wrong_walls": [[0, 1]],
+    });
+    progress::ResumedProgress(Some(serde_json::from_value(json).unwrap()))
+}
+
+#[test]
+fn test_resume_progress() {
+    let mut app = headless_app(level());
+    app.insert_resource(saved_progress(&level()));
+    wait_for_puzzles(&mut app, 1);
+
+    let walls = app
+        .world_mut()
+        .query_filtered::<(), With<Wall>>()
+        .iter(app.world())
+        .count();
+    assert_eq!(walls, 1);
+    assert_eq!(app.world().resource::<check::Mistakes>().count(), 1);
+}
+
+#[test]
+fn test_fixed_level_ignores_other_progress() {
+    let mut app = headless_app(level());
+    app.insert_resource(saved_progress(&Level::random_with_seed(8, 8, 3).unwrap()));
+    wait_for_puzzles(&mut app, 1);
+
+    assert!(events::<PuzzleStarted>(&app)[0].level == level());
+    let walls = app
+        .world_mut()
+        .query_filtered::<(), With<Wall>>()
+        .iter(app.world())
+        .count();
+    assert_eq!(walls, 0);
+}
+
+#[test]
+fn test_generated_level() {
+    let mut app = headless_app_with(DungeonsAndDiagramsPlugin::new(5, 5));
+    wait_for_puzzles(&mut app, 1);
+
+    let started = events::<PuzzleStarted>(&app);
+    assert_eq!(started[0].level.width(), 5);
+    assert_eq!(started[0].level.height(), 5);
+    assert!(!app.world().contains_resource::<generation::Generation>());
+}
+
+#[test]
+fn test_cancel_generation() {
+    let mut app = headless_app_with(
+        DungeonsAndDiagramsPlugin::new(10, 10)
+            .with_shared_puzzle(Puzzle::from_level(&level()))
+            .with_queued_puzzles(0),
+    );
+    wait_for_puzzles(&mut app, 1);
+    set_cell(&mut app, 1, 0, Mark::Wall);
+
+    // A level that never finishes generating, so the cancel doesn't race it
+    let (sender, receiver) = std::sync::mpsc::channel();
+    app.world_mut()
+        .resource_mut::<queue::PuzzleQueue>()
+        .add_generating((10, 10), generation::PendingLevel::new(receiver));
+
+    app.world_mut().send_event(NewPuzzle);
+    let back = (0..MAX_LOADING_FRAMES)
+        .find_map(|_| {
+            app.update();
+            app.world_mut()
+                .query_filtered::<Entity, With<generation::BackButton>>()
+                .iter(app.world())
+                .next()
+        })
+        .expect("Generating did not start");
+    *app.world_mut().get_mut::<Interaction>(back).unwrap() = Interaction::Pressed;
+    wait_for_puzzles(&mut app, 2);
+
+    assert!(events::<PuzzleStarted>(&app)[1].level == level());
+    let walls = app
+        .world_mut()
+        .query_filtered::<(), With<Wall>>()
+        .iter(app.world())
+        .count();
+    assert_eq!(walls, 1);
+
+    // The cancelled level is kept for a later game
+    sender
+        .send(Ok((level(), Solver::from_level(&level()).difficulty())))
+        .unwrap();
+    settle(&mut app);
+    let queue = app.world().resource::<queue::PuzzleQueue>();
+    assert_eq!(queue.ready_count((10, 10)), 1);
+}
+
+#[test]
+fn test_cancel_first_generation() {
+    let mut app = headless_app_with(DungeonsAndDiagramsPlugin::new(10, 10).with_queued_puzzles(0));
+    let (_sender, receiver) = std::sync::mpsc::channel();
+    app.world_mut()
+        .resource_mut::<queue::PuzzleQueue>()
+        .add_generating((10, 10), generation::PendingLevel::new(receiver));
+
+    // Without a game to go back to, a smaller level is played instead
+    let back = (0..MAX_LOADING_FRAMES)
+        .find_map(|_| {
+            app.update();
+            app.world_mut()
+                .query_filtered::<Entity, With<generation::BackButton>>()
+                .iter(app.world())
+                .next()
+        })
+        .expect("Generating did not start");
+    *app.world_mut().get_mut::<Interaction>(back).unwrap() = Interaction::Pressed;
+    wait_for_puzzles(&mut app, 1);
+
+    let level = &events::<PuzzleStarted>(&app)[0].level;
+    assert_eq!((level.width(), level.height()), queue::FALLBACK_LEVEL_SIZE);
+}
+
+#[test]
+fn test_queued_puzzle() {
+    let mut app = headless_app_with(DungeonsAndDiagramsPlugin::new(5, 5).with_queued_puzzles(2));
+    wait_for_puzzles(&mut app, 1);
+
+    let queued = |app: &App| {
+        app.world()
+            .resource::<queue::PuzzleQueue>()
+            .ready_count((5, 5))
+    };
+    for _ in 0..MAX_LOADING_FRAMES {
+        if queued(&app) == 2 {
+            break;
+        }
+        app.update();
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(queued(&app), 2);
+
+    // The next level is one from the queue, so it is played without generating it
+    app.world_mut().send_event(NewPuzzle);
+    for _ in 0..MAX_LOADING_FRAMES {
+        app.update();
+        assert!(!app.world().contains_resource::<generation::Generation>());
+        if events::<PuzzleStarted>(&app).len() == 2 {
+            return;
+        }
+        std::thread::sleep(Duration::from_millis(5));
+    }
+    panic!("Level did not finish loading");
+}
@@ -0,0 +1,316 @@
+use std::cmp::Ordering;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    keyboard::CursorHighlight,
+    stats::{self, TimerText},
+    storage::Storage,
+    toolbar::{ToolbarButton, ToolbarMessage, ToolbarPressed},
+    AppState, Border, Cell, GameComponent, HallwayMark, HeaderStatus, QuestionMark, Wall,
+    UNIT_SIZE,
+};
+
+const THEME_KEY: &str = "theme";
+
+const HEADER_CUE_SIZE: Vec2 = Vec2::new(UNIT_SIZE * 0.5, UNIT_SIZE * 0.06);
+
+pub(crate) fn plugin(app: &mut App) {
+    let name = app
+        .world()
+        .resource::<Storage>()
+        .load::<ThemeName>(THEME_KEY);
+    app.insert_resource(Theme::new(name.unwrap_or_default()))
+        .init_resource::<ClearColor>()
+        .add_systems(
+            Update,
+            cycle_theme
+                .run_if(in_state(AppState::Playing))
+                .run_if(not(stats::is_stats_page_open)),
+        )
+        .add_systems(
+            PostUpdate,
+            (
+                apply_theme.run_if(resource_changed::<Theme>),
+                update_header_cues,
+            ),
+        );
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub(crate) enum ThemeName {
+    #[default]
+    Dark,
+    Light,
+    ColorBlind,
+}
+
+impl ThemeName {
+    fn next(self) -> Self {
+        match self {
+            ThemeName::Dark => ThemeName::Light,
+            ThemeName::Light => ThemeName::ColorBlind,
+            ThemeName::ColorBlind => ThemeName::Dark,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            ThemeName::Dark => "Dark",
+            ThemeName::Light => "Light",
+            ThemeName::ColorBlind => "Colour-blind",
+        }
+    }
+}
+
+/// Colours of the board, picked by the player and saved between sessions
+#[derive(Resource, Clone, Copy, Debug)]
+pub(crate) struct Theme {
+    pub(crate) name: ThemeName,
+    pub(crate) background: Color,
+    pub(crate) text: Color,
+    pub(crate) border: Color,
+    pub(crate) cell: Color,
+    pub(crate) wall: Color,
+    pub(crate) hallway_mark: Color,
+    pub(crate) question_mark: Color,
+    pub(crate) header_too_few: Color,
+    pub(crate) header_match: Color,
+    pub(crate) header_too_many: Color,
+    /// Underline matching headers and strike out headers with too many walls, so
+    /// the header state doesn't depend on colour alone
+    pub(crate) header_cues: bool,
+    /// Overlays on the cells, drawn with the alpha of the colour
+    pub(crate) mistake: Color,
+    pub(crate) violation: Color,
+    pub(crate) cut_off: Color,
+    pub(crate) hint_highlight: Color,
+    pub(crate) cursor: Color,
+    /// Background of pages drawn over the board
+    pub(crate) page: Color,
+    pub(crate) button: Color,
+    pub(crate) button_hovered: Color,
+    pub(crate) button_text: Color,
+}
+
+impl Theme {
+    pub(crate) fn new(name: ThemeName) -> Self {
+        match name {
+            ThemeName::Dark => Theme {
+                name,
+                background: Color::srgb_u8(43, 44, 47),
+                text: Color::srgb(1.0, 1.0, 1.0),
+                border: Color::srgb(0.0, 0.0, 0.0),
+                cell: Color::srgb(0.5, 0.5, 0.5),
+                wall: Color::srgb(0.0, 0.0, 1.0),
+                hallway_mark: Color::srgb(0.9, 0.9, 0.9),
+                question_mark: Color::srgb(1.0, 0.5, 0.0),
+                header_too_few: Color::srgb(0.5, 0.5, 1.0),
+                header_match: Color::srgb(0.0, 1.0, 0.0),
+                header_too_many: Color::srgb(1.0, 0.0, 0.0),
+                header_cues: false,
+                mistake: Color::srgba(1.0, 0.0, 0.0, 0.6),
+                violation: Color::srgba(1.0, 0.0, 0.0, 0.4),
+                cut_off: Color::srgba(0.6, 0.0, 1.0, 0.4),
+                hint_highlight: Color::srgba(1.0, 1.0, 0.0, 0.5),
+                cursor: Color::srgba(1.0, 1.0, 1.0, 0.35),
+                page: Color::srgba(0.0, 0.0, 0.0, 0.85),
+                button: Color::srgb(0.25, 0.25, 0.25),
+                button_hovered: Color::srgb(0.35, 0.35, 0.35),
+                button_text: Color::srgb(1.0, 1.0, 1.0),
+            },
+            ThemeName::Light => Theme {
+                name,
+                background: Color::srgb(0.95, 0.95, 0.92),
+                text: Color::srgb(0.1, 0.1, 0.1),
+                border: Color::srgb(0.35, 0.35, 0.35),
+                cell: Color::srgb(0.85, 0.85, 0.82),
+                wall: Color::srgb(0.15, 0.25, 0.6),
+                hallway_mark: Color::srgb(0.4, 0.4, 0.4),
+                question_mark: Color::srgb(0.85, 0.4, 0.0),
+                header_too_few: Color::srgb(0.2, 0.3, 0.7),
+                header_match: Color::srgb(0.0, 0.55, 0.0),
+                header_too_many: Color::srgb(0.8, 0.0, 0.0),
+                header_cues: false,
+                mistake: Color::srgba(0.85, 0.0, 0.0, 0.55),
+                violation: Color::srgba(0.9, 0.1, 0.1, 0.35),
+                cut_off: Color::srgba(0.5, 0.0, 0.8, 0.35),
+                hint_highlight: Color::srgba(1.0, 0.8, 0.0, 0.5),
+                cursor: Color::srgba(0.0, 0.0, 0.0, 0.25),
+                page: Color::srgba(0.95, 0.95, 0.92, 0.92),
+                button: Color::srgb(0.78, 0.78, 0.75),
+                button_hovered: Color::srgb(0.68, 0.68, 0.65),
+                button_text: Color::srgb(0.1, 0.1, 0.1),
+            },
+            // Okabe-Ito palette, without relying on telling red from green
+            ThemeName::ColorBlind => Theme {
+                name,
+                background: Color::srgb(0.12, 0.12, 0.12),
+                text: Color::srgb(1.0, 1.0, 1.0),
+                border: Color::srgb(0.0, 0.0, 0.0),
+                cell: Color::srgb(0.55, 0.55, 0.55),
+                wall: Color::srgb(0.0, 0.45, 0.7),
+                hallway_mark: Color::srgb(0.95, 0.95, 0.95),
+                question_mark: Color::srgb(0.8, 0.47, 0.65),
+                header_too_few: Color::srgb(0.34, 0.71, 0.91),
+                header_match: Color::srgb(0.94, 0.89, 0.26),
+                header_too_many: Color::srgb(0.84, 0.37, 0.0),
+                header_cues: true,
+                // Vermillion and orange, apart from the bluish green of cut off regions
+                mistake: Color::srgba(0.84, 0.37, 0.0, 0.6),
+                violation: Color::srgba(0.9, 0.6, 0.0, 0.45),
+                cut_off: Color::srgba(0.0, 0.62, 0.45, 0.45),
+                hint_highlight: Color::srgba(0.94, 0.89, 0.26, 0.5),
+                cursor: Color::srgba(1.0, 1.0, 1.0, 0.35),
+                page: Color::srgba(0.0, 0.0, 0.0, 0.85),
+                button: Color::srgb(0.25, 0.25, 0.25),
+                button_hovered: Color::srgb(0.35, 0.35, 0.35),
+                button_text: Color::srgb(1.0, 1.0, 1.0),
+            },
+        }
+    }
+
+    /// Background of a button, from whether the pointer is on it
+    pub(crate) fn button_color(&self, interaction: Interaction) -> Color {
+        match interaction {
+            Interaction::Pressed | Interaction::Hovered => self.button_hovered,
+            Interaction::None => self.button,
+        }
+    }
+
+    /// Colour of a header, from how the walls in its line compare to its count
+    pub(crate) fn header_color(&self, status: Ordering) -> Color {
+        match status {
+            Ordering::Less => self.header_too_few,
+            Ordering::Equal => self.header_match,
+            Ordering::Greater => self.header_too_many,
+        }
+    }
+}
+
+/// Bar drawn on a header to show its state without colour
+#[derive(Component)]
+struct HeaderCue;
+
+pub(crate) fn spawn_header_cue(parent: &mut ChildBuilder) {
+    parent.spawn((
+        GameComponent,
+        HeaderCue,
+        SpriteBundle {
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+    ));
+}
+
+// V or the toolbar button switches to the next theme
+fn cycle_theme(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut toolbar_events: EventReader<ToolbarPressed>,
+    storage: Res<Storage>,
+    mut theme: ResMut<Theme>,
+    mut q_message: Query<&mut Text, With<ToolbarMessage>>,
+) {
+    let pressed = toolbar_events
+        .read()
+        .filter(|event| ***event == ToolbarButton::Theme)
+        .count()
+        > 0;
+    if !pressed && !keys.just_pressed(KeyCode::KeyV) {
+        return;
+    }
+
+    *theme = Theme::new(theme.name.next());
+    storage.save(THEME_KEY, &theme.name);
+    for mut text in &mut q_message {
+        text.sections[0].value = format!("{} theme", theme.name.label());
+    }
+}
+
+// Recolours everything already spawned. New entities are spawned with the theme colours,
+// and the overlays that come and go pick them up when they are next shown.
+fn apply_theme(
+    theme: Res<Theme>,
+    mut clear_color: ResMut<ClearColor>,
+    mut q_sprites: ParamSet<(
+        Query<&mut Sprite, With<Border>>,
+        Query<&mut Sprite, With<Cell>>,
+        Query<&mut Sprite, With<Wall>>,
+        Query<&mut Sprite, With<HallwayMark>>,
+        Query<&mut Sprite, With<CursorHighlight>>,
+    )>,
+    mut q_texts: ParamSet<(
+        Query<&mut Text, With<QuestionMark>>,
+        Query<&mut Text, Or<(With<TimerText>, With<ToolbarMessage>)>>,
+        Query<&mut Text>,
+    )>,
+    mut q_buttons: Query<(&Interaction, &mut BackgroundColor, &Children), With<Button>>,
+) {
+    clear_color.0 = theme.background;
+
+    for mut sprite in &mut q_sprites.p0() {
+        sprite.color = theme.border;
+    }
+    for mut sprite in &mut q_sprites.p1() {
+        sprite.color = theme.cell;
+    }
+    for mut sprite in &mut q_sprites.p2() {
+        sprite.color = theme.wall;
+    }
+    for mut sprite in &mut q_sprites.p3() {
+        sprite.color = theme.hallway_mark;
+    }
+    for mut sprite in &mut q_sprites.p4() {
+        sprite.color = theme.cursor;
+    }
+    for mut text in &mut q_texts.p0() {
+        text.sections[0].style.color = theme.question_mark;
+    }
+    for mut text in &mut q_texts.p1() {
+        text.sections[0].style.color = theme.text;
+    }
+    for (interaction, mut background_color, children) in &mut q_buttons {
+        *background_color = theme.button_color(*interaction).into();
+        let mut q_labels = q_texts.p2();
+        let mut labels = q_labels.iter_many_mut(children);
+        while let Some(mut text) = labels.fetch_next() {
+            text.sections[0].style.color = theme.button_text;
+        }
+    }
+}
+
+fn update_header_cues(
+    theme: Res<Theme>,
+    q_headers: Query<(&HeaderStatus, &Children)>,
+    mut q_cues: Query<(&mut Transform, &mut Sprite, &mut Visibility), With<HeaderCue>>,
+) {
+    for (status, children) in &q_headers {
+        let mut cues = q_cues.iter_many_mut(children);
+        while let Some((mut transform, mut sprite, mut visibility)) = cues.fetch_next() {
+            let placement = match status.0 {
+                _ if !theme.header_cues => None,
+                Ordering::Less => None,
+                // Underline
+                Ordering::Equal => Some((Vec3::new(0.0, -UNIT_SIZE * 0.38, 0.1), 0.0)),
+                // Strike through
+                Ordering::Greater => Some((Vec3::new(0.0, 0.0, 0.1), std::f32::consts::FRAC_PI_4)),
+            };
+
+            let Some((translation, angle)) = placement else {
+                visibility.set_if_neq(Visibility::Hidden);
+                continue;
+            };
+            visibility.set_if_neq(Visibility::Inherited);
+            transform.set_if_neq(Transform {
+                translation,
+                rotation: Quat::from_rotation_z(angle),
+                scale: HEADER_CUE_SIZE.extend(0.0),
+            });
+            let color = theme.header_color(status.0);
+            if sprite.color != color {
+                sprite.color = color;
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use bevy::prelude::*;
+
+use crate::{theme::Theme, AppState, GameComponent};
+
+pub(crate) const TOOLBAR_TEXT_SIZE: f32 = 24.0;
+
+pub(crate) fn plugin(app: &mut App) {
+    app.add_event::<ToolbarPressed>()
+        .add_systems(OnExit(AppState::Loading), spawn_toolbar)
+        .add_systems(
+            Update,
+            handle_toolbar_buttons.run_if(in_state(AppState::Playing)),
+        );
+}
+
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ToolbarButton {
+    Undo,
+    Hint,
+    Check,
+    AutoCheck,
+    AutoFill,
+    Warnings,
+    Theme,
+    Stats,
+    Daily,
+}
+
+impl ToolbarButton {
+    fn label(&self) -> &'static str {
+        match self {
+            ToolbarButton::Undo => "Undo",
+            ToolbarButton::Hint => "Hint",
+            ToolbarButton::Check => "Check",
+            ToolbarButton::AutoCheck => "Auto-check",
+            ToolbarButton::AutoFill => "Auto-fill",
+            ToolbarButton::Warnings => "Warnings",
+            ToolbarButton::Theme => "Theme",
+            ToolbarButton::Stats => "Stats",
+            ToolbarButton::Daily => "Daily",
+        }
+    }
+}
+
+/// Container of the toolbar buttons, placed by the layout
+#[derive(Component)]
+pub(crate) struct Toolbar;
+
+#[derive(Event, Clone, Copy, Deref)]
+pub(crate) struct ToolbarPressed(pub(crate) ToolbarButton);
+
+/// Text next to the toolbar buttons, used for short status messages
+#[derive(Component)]
+pub(crate) struct ToolbarMessage;
+
+fn spawn_toolbar(mut commands: Commands, theme: Res<Theme>) {
+    let text_style = TextStyle {
+        font_size: TOOLBAR_TEXT_SIZE,
+        color: theme.button_text,
+        ..Default::default()
+    };
+
+    commands
+        .spawn((
+            GameComponent,
+            Toolbar,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(8.0),
+                    right: Val::Px(8.0),
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(8.0),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                GameComponent,
+                ToolbarMessage,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        color: theme.text,
+                        ..text_style.clone()
+                    },
+                ),
+            ));
+
+            for button in [
+                ToolbarButton::Undo,
+                ToolbarButton::Hint,
+                ToolbarButton::Check,
+                ToolbarButton::AutoCheck,
+                ToolbarButton::AutoFill,
+                ToolbarButton::Warnings,
+                ToolbarButton::Theme,
+                ToolbarButton::Stats,
+                ToolbarButton::Daily,
+            ] {
+                spawn_button(parent, button, &theme, &text_style);
+            }
+        });
+}
+
+fn spawn_button(
+    parent: &mut ChildBuilder,
+    button: ToolbarButton,
+    theme: &Theme,
+    text_style: &TextStyle,
+) {
+    parent
+        .spawn((
+            GameComponent,
+            button,
+            ButtonBundle {
+                style: Style {
+                    padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                    ..Default::default()
+                },
+                background_color: theme.button.into(),
+                ..Default::default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                GameComponent,
+                TextBundle::from_section(button.label(), text_style.clone()),
+            ));
+        });
+}
+
+/// Run condition for world input, so clicks on the toolbar don't reach the board
+pub(crate) fn is_pointer_over_toolbar(q_buttons: Query<&Interaction, With<ToolbarButton>>) -> bool {
+    q_buttons
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+}
+
+fn handle_toolbar_buttons(
+    theme: Res<Theme>,
+    mut events: EventWriter<ToolbarPressed>,
+    mut q_buttons: Query<
+        (&ToolbarButton, &Interaction, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+) {
+    for (button, interaction, mut background_color) in &mut q_buttons {
+        if *interaction == Interaction::Pressed {
+            events.send(ToolbarPressed(*button));
+        }
+        *background_color = theme.button_color(*interaction).into();
+    }
+}
@@ -0,0 +1,245 @@
+//! The level generator and solver for JavaScript, to use puzzles outside the game canvas.
+//!
+//! Levels and solutions are passed as text, one line per row with `#` for walls, `.` for
+//! hallways, `M` for monsters and `T` for treasures. Puzzles are read and written in the
+//! pack format, as share codes or as JSON.
+
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use dnd_rs_level::{Difficulty, Level, LevelBuilder, Puzzle, Solver};
+
+#[wasm_bindgen(js_name = LevelBuilder)]
+pub struct JsLevelBuilder(LevelBuilder);
+
+#[wasm_bindgen(js_class = LevelBuilder)]
+impl JsLevelBuilder {
+    #[wasm_bindgen(constructor)]
+    pub fn new(width: usize, height: usize) -> Self {
+        Self(LevelBuilder::new(width, height))
+    }
+
+    pub fn seed(self, seed: u64) -> Self {
+        Self(self.0.seed(seed))
+    }
+
+    #[wasm_bindgen(js_name = checkUniqueSolution)]
+    pub fn check_unique_solution(self) -> Self {
+        Self(self.0.check_unique_solution())
+    }
+
+    #[wasm_bindgen(js_name = checkTooManyWalls)]
+    pub fn check_too_many_walls(self) -> Self {
+        Self(self.0.check_too_many_walls())
+    }
+
+    /// A new puzzle, with its solution
+    pub fn build(&self) -> Result<JsPuzzle, JsError> {
+        let level = self.0.build().map_err(JsError::new)?;
+        Ok(JsPuzzle(Puzzle::from_level(&level)))
+    }
+}
+
+#[wasm_bindgen(js_name = Puzzle)]
+pub struct JsPuzzle(Puzzle);
+
+#[wasm_bindgen(js_class = Puzzle)]
+impl JsPuzzle {
+    /// Reads a puzzle in the pack format
+    #[wasm_bindgen(js_name = fromText)]
+    pub fn from_text(text: &str) -> Result<JsPuzzle, JsError> {
+        Puzzle::try_from(text)
+            .map(Self)
+            .map_err(|err| JsError::new(&err))
+    }
+
+    #[wasm_bindgen(js_name = fromShareCode)]
+    pub fn from_share_code(code: &str) -> Result<JsPuzzle, JsError> {
+        Puzzle::from_share_code(code)
+            .map(Self)
+            .map_err(|err| JsError::new(&err))
+    }
+
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<JsPuzzle, JsError> {
+        let json: PuzzleJson = serde_json::from_str(json)?;
+        json.try_into().map(Self).map_err(|err| JsError::new(&err))
+    }
+
+    /// The puzzle in the pack format
+    #[wasm_bindgen(js_name = toText)]
+    pub fn to_text(&self) -> String {
+        self.0.to_string()
+    }
+
+    #[wasm_bindgen(js_name = toShareCode)]
+    pub fn to_share_code(&self) -> String {
+        self.0.to_share_code()
+    }
+
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, JsError> {
+        Ok(serde_json::to_string(&PuzzleJson::from(&self.0))?)
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn width(&self) -> usize {
+        self.0.width()
+    }
+
+    #[wasm_bindgen(getter)]
+    pub fn height(&self) -> usize {
+        self.0.height()
+    }
+
+    /// The stored solution, if any
+    #[wasm_bindgen(getter)]
+    pub fn solution(&self) -> Option<String> {
+        self.0.solution.as_ref().map(level_text)
+    }
+
+    pub fn solver(&self) -> JsSolver {
+        JsSolver(self.0.solver())
+    }
+}
+
+#[wasm_bindgen(js_name = Solver)]
+pub struct JsSolver(Solver);
+
+#[wasm_bindgen(js_class = Solver)]
+impl JsSolver {
+    /// Reads a puzzle in the solver format, which may also have walls and hallways placed
+    #[wasm_bindgen(js_name = fromText)]
+    pub fn from_text(text: &str) -> Result<JsSolver, JsError> {
+        Solver::try_from(text)
+            .map(Self)
+            .map_err(|err| JsError::new(&err))
+    }
+
+    #[wasm_bindgen(js_name = firstSolution)]
+    pub fn first_solution(&self) -> Option<String> {
+        self.0.clone().first_solution().as_ref().map(level_text)
+    }
+
+    #[wasm_bindgen(js_name = allSolutions)]
+    pub fn all_solutions(&self) -> Vec<String> {
+        self.0
+            .clone()
+            .all_solutions()
+            .iter()
+            .map(level_text)
+            .collect()
+    }
+
+    /// `"Easy"`, `"Medium"` or `"Hard"`
+    pub fn difficulty(&self) -> String {
+        self.0.clone().difficulty().to_string()
+    }
+
+    /// Whether the solved level in the text is a solution to the puzzle, for example to
+    /// verify a submitted solve
+    #[wasm_bindgen(js_name = isSolution)]
+    pub fn is_solution(&self, level: &str) -> Result<bool, JsError> {
+        let level = Level::try_from(level).map_err(|err| JsError::new(&err))?;
+        Ok(self.0.clone().all_solutions().contains(&level))
+    }
+}
+
+fn level_text(level: &Level) -> String {
+    format!("{level:?}")
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PuzzleJson {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    author: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    seed: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    difficulty: Option<String>,
+    row_walls: Vec<usize>,
+    col_walls: Vec<usize>,
+    /// `[x, y]` positions
+    #[serde(default)]
+    monsters: Vec<(usize, usize)>,
+    #[serde(default)]
+    treasures: Vec<(usize, usize)>,
+    /// The solved level, one string per row
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    solution: Option<Vec<String>>,
+}
+
+impl From<&Puzzle> for PuzzleJson {
+    fn from(puzzle: &Puzzle) -> Self {
+        PuzzleJson {
+            title: puzzle.title.clone(),
+            author: puzzle.author.clone(),
+            seed: puzzle.seed,
+            difficulty: puzzle.difficulty.map(|d| d.to_string()),
+            row_walls: puzzle.row_walls().to_vec(),
+            col_walls: puzzle.col_walls().to_vec(),
+            monsters: puzzle.monsters(),
+            treasures: puzzle.treasures(),
+            solution: puzzle
+                .solution
+                .as_ref()
+                .map(|level| level_text(level).lines().map(str::to_string).collect()),
+        }
+    }
+}
+
+impl TryFrom<PuzzleJson> for Puzzle {
+    type Error = String;
+
+    fn try_from(json: PuzzleJson) -> Result<Self, Self::Error> {
+        let mut puzzle = Puzzle::new(
+            json.row_walls,
+            json.col_walls,
+            &json.monsters,
+            &json.treasures,
+        )?;
+        puzzle.title = json.title;
+        puzzle.author = json.author;
+        puzzle.seed = json.seed;
+        puzzle.difficulty = json
+            .difficulty
+            .map(|d| d.parse::<Difficulty>())
+            .transpose()?;
+        if let Some(rows) = json.solution {
+            let level = Level::try_from(rows.join("\n").as_str())?;
+            if !Puzzle::from_level(&level).has_same_board(&puzzle) {
+                return Err("Solution does not match the puzzle".to_string());
+            }
+            puzzle.solution = Some(level);
+        }
+        Ok(puzzle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_round_trip() {
+        let level = Level::random_with_seed(6, 5, 3).unwrap();
+        let mut puzzle = Puzzle::from_level(&level);
+        puzzle.title = Some("Test".to_string());
+        puzzle.seed = Some(3);
+
+        let json = serde_json::to_string(&PuzzleJson::from(&puzzle)).unwrap();
+        let json: PuzzleJson = serde_json::from_str(&json).unwrap();
+        assert!(Puzzle::try_from(json).unwrap() == puzzle);
+
+        let json = r#"{"rowWalls": [1, 0], "colWalls": [0, 1], "monsters": [[0, 0]]}"#;
+        let puzzle = Puzzle::try_from(serde_json::from_str::<PuzzleJson>(json).unwrap()).unwrap();
+        assert!(puzzle.is_monster(0, 0));
+        assert!(puzzle.solution.is_none());
+
+        let json = r#"{"rowWalls": [1, 0], "colWalls": [0, 1], "monsters": [[2, 0]]}"#;
+        assert!(Puzzle::try_from(serde_json::from_str::<PuzzleJson>(json).unwrap()).is_err());
+    }
+}
@@ -1,14 +1,77 @@
+mod bindings;
+#[cfg(target_feature = "atomics")]
+mod threads;
 mod utils;
 
 use bevy::prelude::*;
-use dnd_rs_plugin::DungeonsAndDiagramsPlugin;
+use dnd_rs_plugin::{DungeonsAndDiagramsPlugin, Level, LevelSource, Puzzle};
 use wasm_bindgen::prelude::*;
 
+pub use bindings::{JsLevelBuilder, JsPuzzle, JsSolver};
+#[cfg(target_feature = "atomics")]
+pub use threads::init_thread_pool;
+
 #[wasm_bindgen]
 pub fn run() {
     utils::set_panic_hook();
 
-    App::new()
-        .add_plugins((DefaultPlugins, DungeonsAndDiagramsPlugin::default()))
-        .run();
+    let mut plugin = DungeonsAndDiagramsPlugin::default();
+    if let Some(puzzle) = shared_puzzle() {
+        plugin = plugin.with_shared_puzzle(puzzle);
+    }
+
+    App::new().add_plugins((DefaultPlugins, plugin)).run();
+}
+
+/// Runs the game with random levels of the given size, starting with the puzzle from the
+/// share code or seed if there is one. The URL fragment is not read.
+#[wasm_bindgen(js_name = runWithOptions)]
+pub fn run_with_options(
+    width: Option<usize>,
+    height: Option<usize>,
+    seed: Option<u64>,
+    share_code: Option<String>,
+) -> Result<(), JsError> {
+    utils::set_panic_hook();
+
+    let mut plugin = DungeonsAndDiagramsPlugin::default();
+    if width.is_some() || height.is_some() {
+        plugin = plugin.with_level_source(LevelSource::Random {
+            width: width.unwrap_or(8),
+            height: height.unwrap_or(8),
+        });
+    }
+    if let Some(code) = share_code {
+        let puzzle = Puzzle::from_share_code(&code).map_err(|err| JsError::new(&err))?;
+        plugin = plugin.with_shared_puzzle(puzzle);
+    } else if let Some(seed) = seed {
+        let level = Level::builder(width.unwrap_or(8), height.unwrap_or(8))
+            .seed(seed)
+            .build()
+            .map_err(JsError::new)?;
+        let mut puzzle = Puzzle::from_level(&level);
+        puzzle.seed = Some(seed);
+        plugin = plugin.with_shared_puzzle(puzzle);
+    }
+
+    App::new().add_plugins((DefaultPlugins, plugin)).run();
+    Ok(())
+}
+
+// A share code in the URL fragment, as in `https://dnd.bjarkebjarke.dk/#<code>`
+fn shared_puzzle() -> Option<Puzzle> {
+    let hash = web_sys::window()?.location().hash().ok()?;
+    let code = hash.trim_start_matches('#');
+    if code.is_empty() {
+        return None;
+    }
+
+    match Puzzle::from_share_code(code) {
+        Ok(puzzle) => Some(puzzle),
+        Err(err) => {
+            // Logging is not set up before the app is built
+            web_sys::console::warn_1(&format!("Could not read share code: {err}").into());
+            None
+        }
+    }
 }
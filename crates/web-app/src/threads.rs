@@ -0,0 +1,85 @@
+//! A rayon thread pool over Web Workers, for builds with the `atomics` target feature.
+//!
+//! Each worker loads the app with the shared memory and runs one of the pool's threads.
+//! Building the pool waits for its threads to start, which the main thread is not allowed
+//! to do, so that also happens on a worker of its own.
+
+use std::sync::{
+    mpsc::{self, Receiver, Sender},
+    LazyLock, Mutex,
+};
+
+use js_sys::Promise;
+use rayon::{ThreadBuilder, ThreadPoolBuilder};
+use wasm_bindgen::prelude::*;
+
+#[wasm_bindgen(module = "/src/workers.js")]
+extern "C" {
+    #[wasm_bindgen(js_name = startWorkers)]
+    fn start_workers(
+        app_url: &JsValue,
+        module: JsValue,
+        memory: JsValue,
+        num_threads: usize,
+    ) -> Promise;
+}
+
+#[wasm_bindgen]
+extern "C" {
+    /// URL of the app's JavaScript module, as this import ends up in it, for the workers to
+    /// load the app from wherever and under whatever name it was built
+    #[wasm_bindgen(thread_local, js_namespace = ["import", "meta"], js_name = url)]
+    static APP_URL: JsValue;
+}
+
+/// Threads from the pool builder, waiting for a worker to run them
+struct Threads {
+    sender: Mutex<Sender<ThreadBuilder>>,
+    receiver: Mutex<Receiver<ThreadBuilder>>,
+}
+
+static THREADS: LazyLock<Threads> = LazyLock::new(|| {
+    let (sender, receiver) = mpsc::channel();
+    Threads {
+        sender: Mutex::new(sender),
+        receiver: Mutex::new(receiver),
+    }
+});
+
+/// Starts the thread pool the solver uses. Call it once after `init` and wait for it before
+/// generating levels.
+#[wasm_bindgen(js_name = initThreadPool)]
+pub fn init_thread_pool(num_threads: usize) -> Promise {
+    APP_URL.with(|app_url| {
+        start_workers(
+            app_url,
+            wasm_bindgen::module(),
+            wasm_bindgen::memory(),
+            num_threads,
+        )
+    })
+}
+
+#[doc(hidden)]
+#[wasm_bindgen(js_name = buildThreadPool)]
+pub fn build_thread_pool(num_threads: usize) -> Result<(), JsError> {
+    ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .spawn_handler(|thread| {
+            THREADS
+                .sender
+                .lock()
+                .unwrap()
+                .send(thread)
+                .map_err(|_| std::io::Error::other("Thread pool workers are gone"))
+        })
+        .build_global()?;
+    Ok(())
+}
+
+#[doc(hidden)]
+#[wasm_bindgen(js_name = runPoolThread)]
+pub fn run_pool_thread() {
+    let thread = THREADS.receiver.lock().unwrap().recv().unwrap_throw();
+    thread.run();
+}
@@ -0,0 +1,54 @@
+// Web Workers for the thread pool in `threads.rs`. The app imports this module for
+// `startWorkers`, and every worker runs it to load the app and take its part.
+
+function waitForMessage(target, type) {
+  return new Promise((resolve) => {
+    target.addEventListener('message', function onMessage({ data }) {
+      if (data?.type === type) {
+        target.removeEventListener('message', onMessage);
+        resolve(data);
+      }
+    });
+  });
+}
+
+function startWorker(init, message) {
+  const worker = new Worker(new URL('./workers.js', import.meta.url), { type: 'module' });
+  worker.postMessage({ type: 'dnd_worker_init', init, ...message });
+  return worker;
+}
+
+export async function startWorkers(appUrl, module, memory, numThreads) {
+  const init = { module_or_path: module, memory };
+  await Promise.all(
+    Array.from({ length: numThreads }, () =>
+      waitForMessage(startWorker(init, { role: 'thread', appUrl }), 'dnd_worker_ready'),
+    ),
+  );
+
+  const builder = startWorker(init, { role: 'build', appUrl, numThreads });
+  const { error } = await waitForMessage(builder, 'dnd_pool_built');
+  builder.terminate();
+  if (error) {
+    throw new Error(error);
+  }
+}
+
+if (typeof WorkerGlobalScope !== 'undefined' && self instanceof WorkerGlobalScope) {
+  waitForMessage(self, 'dnd_worker_init').then(async ({ init, role, appUrl, numThreads }) => {
+    const app = await import(appUrl);
+    await app.default(init);
+
+    if (role === 'build') {
+      try {
+        app.buildThreadPool(numThreads);
+        postMessage({ type: 'dnd_pool_built' });
+      } catch (err) {
+        postMessage({ type: 'dnd_pool_built', error: String(err) });
+      }
+    } else {
+      postMessage({ type: 'dnd_worker_ready' });
+      app.runPoolThread();
+    }
+  });
+}